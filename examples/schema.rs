@@ -4,7 +4,7 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use nft_auction::msg::{CountResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use nft_auction::state::State;
+use nft_auction::state::{Config, State};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -16,5 +16,6 @@ fn main() {
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(State), &out_dir);
+    export_schema(&schema_for!(Config), &out_dir);
     export_schema(&schema_for!(CountResponse), &out_dir);
 }
@@ -1,33 +1,36 @@
 use crate::error::ContractError;
-use cosmwasm_std::{Coin, Uint128};
-
-pub fn assert_sent_sufficient_coin(
-    sent: &[Coin],
-    required: Option<Coin>,
-) -> Result<Option<Coin>, ContractError> {
-    let mut sent_coin: Coin = Coin {
-        denom: String::from("token"),
-        amount: Uint128::from(0u64),
-    };
-    if let Some(required_coin) = required {
-        let required_amount = required_coin.amount.u128();
-        if required_amount > 0 {
-            let sent_sufficient_funds = sent.iter().any(|coin| {
-                // check if a given sent coin matches denom
-                // and has sufficient amount
-                sent_coin = coin.clone();
-                coin.denom == required_coin.denom && coin.amount.u128() > required_amount
-            });
-
-            if sent_sufficient_funds {
-                return Ok(Some(sent_coin));
-            } else {
-                return Err(ContractError::InsufficientFundsSend {});
-            }
-        }
+use cosmwasm_std::Coin;
+
+/// Require exactly one coin in the denom of `required`, of at least its amount.
+/// Coins in any other denom are rejected rather than kept by the contract.
+/// Returns the sent coin.
+pub fn assert_sent_sufficient_coin(sent: &[Coin], required: &Coin) -> Result<Coin, ContractError> {
+    if let Some(stray) = sent.iter().find(|coin| coin.denom != required.denom) {
+        return Err(ContractError::UnexpectedFunds { denom: stray.denom.clone() });
+    }
+    match sent {
+        [coin] if coin.amount >= required.amount => Ok(coin.clone()),
+        [] if required.amount.is_zero() => Ok(Coin::new(0, &required.denom)),
+        _ => Err(ContractError::InsufficientFundsSend {}),
+    }
+}
+
+/// Require exactly one coin, of any denom and a non-zero amount.
+/// Returns the sent coin.
+pub fn assert_sent_one_coin(sent: &[Coin]) -> Result<Coin, ContractError> {
+    match sent {
+        [coin] if !coin.amount.is_zero() => Ok(coin.clone()),
+        [_, stray, ..] => Err(ContractError::UnexpectedFunds { denom: stray.denom.clone() }),
+        _ => Err(ContractError::InsufficientFundsSend {}),
     }
+}
 
-    Ok(Some(sent_coin))
+/// Require that no coins were sent
+pub fn assert_no_funds(sent: &[Coin]) -> Result<(), ContractError> {
+    match sent.first() {
+        Some(stray) => Err(ContractError::UnexpectedFunds { denom: stray.denom.clone() }),
+        None => Ok(()),
+    }
 }
 
 #[cfg(test)]
@@ -37,25 +40,79 @@ mod test {
 
     #[test]
     fn assert_sent_sufficient_coin_works() {
-        match assert_sent_sufficient_coin(&[], Some(coin(0, "token"))) {
-            Ok(_) => {}
+        match assert_sent_sufficient_coin(&[], &coin(0, "token")) {
+            Ok(coin_val) => assert_eq!(coin(0, "token"), coin_val),
             Err(e) => panic!("Unexpected error: {:?}", e),
         };
 
-        match assert_sent_sufficient_coin(&[], Some(coin(5, "token"))) {
+        match assert_sent_sufficient_coin(&[], &coin(5, "token")) {
             Ok(_) => panic!("Should have raised insufficient funds error"),
             Err(ContractError::InsufficientFundsSend {}) => {}
             Err(e) => panic!("Unexpected error: {:?}", e),
         };
 
-        match assert_sent_sufficient_coin(&coins(10, "smokin"), Some(coin(5, "token"))) {
+        match assert_sent_sufficient_coin(&coins(4, "token"), &coin(5, "token")) {
             Ok(_) => panic!("Should have raised insufficient funds error"),
             Err(ContractError::InsufficientFundsSend {}) => {}
             Err(e) => panic!("Unexpected error: {:?}", e),
         };
 
-        match assert_sent_sufficient_coin(&coins(10, "token"), Some(coin(5, "token"))) {
-            Ok(_) => {}
+        match assert_sent_sufficient_coin(&coins(10, "smokin"), &coin(5, "token")) {
+            Ok(_) => panic!("Should have raised unexpected funds error"),
+            Err(ContractError::UnexpectedFunds { denom }) => assert_eq!("smokin", denom),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        };
+
+        // stray coins are rejected even next to a sufficient one
+        let sent = vec![coin(10, "smokin"), coin(5, "token")];
+        match assert_sent_sufficient_coin(&sent, &coin(5, "token")) {
+            Ok(_) => panic!("Should have raised unexpected funds error"),
+            Err(ContractError::UnexpectedFunds { denom }) => assert_eq!("smokin", denom),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        };
+
+        match assert_sent_sufficient_coin(&coins(10, "token"), &coin(5, "token")) {
+            Ok(coin_val) => assert_eq!(coin(10, "token"), coin_val),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        };
+    }
+
+    #[test]
+    fn assert_sent_one_coin_works() {
+        match assert_sent_one_coin(&[]) {
+            Ok(_) => panic!("Should have raised insufficient funds error"),
+            Err(ContractError::InsufficientFundsSend {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        };
+
+        match assert_sent_one_coin(&coins(0, "token")) {
+            Ok(_) => panic!("Should have raised insufficient funds error"),
+            Err(ContractError::InsufficientFundsSend {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        };
+
+        match assert_sent_one_coin(&[coin(5, "token"), coin(10, "smokin")]) {
+            Ok(_) => panic!("Should have raised unexpected funds error"),
+            Err(ContractError::UnexpectedFunds { denom }) => assert_eq!("smokin", denom),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        };
+
+        match assert_sent_one_coin(&coins(5, "token")) {
+            Ok(coin_val) => assert_eq!(coin(5, "token"), coin_val),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        };
+    }
+
+    #[test]
+    fn assert_no_funds_works() {
+        match assert_no_funds(&[]) {
+            Ok(()) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        };
+
+        match assert_no_funds(&coins(5, "token")) {
+            Ok(_) => panic!("Should have raised unexpected funds error"),
+            Err(ContractError::UnexpectedFunds { denom }) => assert_eq!("token", denom),
             Err(e) => panic!("Unexpected error: {:?}", e),
         };
     }
@@ -1,26 +1,54 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env,
-    MessageInfo, Response, StdResult, Uint128, Addr, SubMsg,
-    Coin, BankMsg, CosmosMsg, StdError
+    attr, coin, to_binary, from_binary, Binary, Coin, Deps, DepsMut, Env,
+    MessageInfo, Order, Response, BlockInfo, StdResult, Uint128, Addr, SubMsg,
+    BankMsg, StdError, WasmMsg, Pair, Reply, ContractResult
 };
+use cw_storage_plus::{Bound, PrimaryKey, U64Key};
 use cw2::set_contract_version;
-use cw0::{Event,Expiration};
+use cw0::{Duration,Event,Expiration};
+use cw20::{Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::convert::TryInto;
 
 use crate::error::ContractError;
 use crate::msg::{
     CountResponse, BalanceResponse, IsApprovedForAllResponse, ExecuteMsg, InstantiateMsg,
-    QueryMsg, TokenId, Cw1155ReceiveMsg, AuctionResponse
+    QueryMsg, TokenId, Cw1155ReceiveMsg, Cw1155BatchReceiveMsg, AuctionResponse, ReceiveMsg,
+    AuctionTerms, AuctionsResponse, AuctionsByStatusResponse, BidsResponse, ClaimableResponse, CommitmentResponse, CommitmentsResponse, UnitBidsResponse, bid_commitment,
+    TokenReceived, Cw20HookMsg, RoyaltyMsg, RoyaltiesInfoResponse, PayeeMsg, ListingResponse, ListingsResponse, OfferResponse, OffersResponse,
+    CollectionOfferResponse, CollectionOffersResponse
 };
-use crate::state::{State, STATE, APPROVES, BALANCES, MINTER, TOKENS, Auction, AUCTIONS};
-use crate::event::{TransferEvent,ApproveAllEvent};
-use crate::coin_helpers::assert_sent_sufficient_coin;
+use crate::state::{
+    State, STATE, APPROVES, BALANCES, MINTER, TOKENS, Auction, AuctionStatus, auctions, indexed_close, AUCTION_COUNT, ESCROWS, ROYALTIES, Royalty, Payee, Listing, listings, LISTING_COUNT, Offer, offers, OFFER_COUNT, CollectionOffer, OfferScope, collection_offers, COLLECTION_OFFER_COUNT, COLLECTION_OFFER_TOKENS, COLLECTION_OFFER_PREFIXES, CLAIMABLE, CLAIMABLE_CW20, BidRecord, bids, BID_COUNT, Config, CONFIG,
+    BidIncrement, AntiSnipe, AuctionKind, DutchAuction, PriceDecay, SealedAuction, SealedPricing,
+    CommitmentPenalty, Commitment, CommitmentStatus, COMMITMENTS, UnitBid, UNIT_BIDS, PendingSale, PENDING_SALES,
+    PENDING_SALE_COUNT
+};
+use crate::event::{TransferEvent,ApproveAllEvent,CancelAuctionEvent};
+use crate::coin_helpers::{assert_no_funds, assert_sent_one_coin, assert_sent_sufficient_coin};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:nft-auction";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// settings for pagination
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+const MAX_PRUNE_LIMIT: u32 = 100;
+const MAX_SCAN_LIMIT: usize = 100;
+
+// bidders per sealed-bid or multi-unit auction, settlement pays out every one of them at once
+const MAX_BIDDERS: usize = 100;
+
+// reply id of tokens of an external collection handed back to their seller
+const RETURN_REPLY_ID: u64 = 0;
+
+// native denoms accepted when none are given at instantiation
+const DEFAULT_DENOMS: [&str; 2] = ["uluna", "uusd"];
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -38,6 +66,25 @@ pub fn instantiate(
     let minter = deps.api.addr_validate(&msg.minter)?;
     MINTER.save(deps.storage, &minter)?;
 
+    let default_min_increment = msg.default_min_increment.unwrap_or_default();
+    validate_bid_increment(&default_min_increment)?;
+    let accepted_denoms = msg.accepted_denoms
+        .unwrap_or_else(|| DEFAULT_DENOMS.iter().map(|denom| denom.to_string()).collect());
+    let cw20_whitelist = msg.cw20_whitelist
+        .unwrap_or_default()
+        .iter()
+        .map(|token| deps.api.addr_validate(token))
+        .collect::<StdResult<Vec<_>>>()?;
+    let protocol_fee_bps = msg.protocol_fee_bps.unwrap_or_default();
+    validate_protocol_fee(protocol_fee_bps)?;
+    let treasury = match msg.treasury {
+        Some(treasury) => deps.api.addr_validate(&treasury)?,
+        None => info.sender.clone(),
+    };
+    CONFIG.save(deps.storage, &Config {
+        default_min_increment, accepted_denoms, cw20_whitelist, protocol_fee_bps, treasury
+    })?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("owner", info.sender)
@@ -52,21 +99,112 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    if !is_payable(&msg) {
+        assert_no_funds(&info.funds)?;
+    }
     match msg {
         ExecuteMsg::Increment {} => try_increment(deps),
         ExecuteMsg::Reset { count } => try_reset(deps, info, count),
 
-        ExecuteMsg::Mint { to, token_id, value, msg } => execute_mint(deps, env, info, to, token_id, value, msg),
+        ExecuteMsg::Mint { to, token_id, value, msg, royalty } => execute_mint(deps, env, info, to, token_id, value, msg, royalty),
+        ExecuteMsg::UpdateRoyalty { token_id, royalty } => execute_update_royalty(deps, env, info, token_id, royalty),
         ExecuteMsg::SendFrom { from, to, token_id, value, msg } => execute_send_from(deps, env, info, from, to, token_id, value, msg),
         ExecuteMsg::ApproveAll { operator, expires } => execute_approve_all(deps, env, info, operator, expires),
         ExecuteMsg::RevokeAll { operator } => execute_revoke_all(deps, env, info, operator),
 
         ExecuteMsg::CreateAuction {
-            token_id, amount, price, seller, bidding_close
-        } => execute_create_auction(deps, env, info, token_id, amount, price, seller, bidding_close),
-        ExecuteMsg::Bid { token_id, seller } => execute_bid(deps, env, info, token_id, seller),
-        ExecuteMsg::CloseAuction { token_id, seller } => execute_auction_close(deps, env, info, token_id, seller),
+            token_id, amount, reserve_price, seller, bidding_start, bidding_close, min_increment, anti_snipe, buy_now_price, kind, payees
+        } => execute_create_auction(deps, env, info, token_id, amount, seller, AuctionTerms {
+            reserve_price, bidding_start, bidding_close, min_increment, anti_snipe, buy_now_price, kind, payees
+        }),
+        ExecuteMsg::Bid { auction_id } => execute_bid(deps, env, info, auction_id),
+        ExecuteMsg::CloseAuction { auction_id } => execute_auction_close(deps, env, info, auction_id),
+        ExecuteMsg::CancelAuction { auction_id } => execute_cancel_auction(deps, env, info, auction_id),
+        ExecuteMsg::CommitBid { auction_id, commitment } => execute_commit_bid(deps, env, info, auction_id, commitment),
+        ExecuteMsg::RevealBid {
+            auction_id, amount, salt
+        } => execute_reveal_bid(deps, env, info, auction_id, amount, salt),
+        ExecuteMsg::BidUnits {
+            auction_id, quantity, unit_price
+        } => execute_bid_units(deps, env, info, auction_id, quantity, unit_price),
+        ExecuteMsg::PruneBids { auction_id, limit } => execute_prune_bids(deps, env, info, auction_id, limit),
+        ExecuteMsg::Withdraw { denom } => execute_withdraw(deps, env, info, denom),
+        ExecuteMsg::WithdrawAll {} => execute_withdraw_all(deps, env, info),
+        ExecuteMsg::UpdateAcceptedDenoms { add, remove } => execute_update_accepted_denoms(deps, env, info, add, remove),
+        ExecuteMsg::UpdateCw20Whitelist { add, remove } => execute_update_cw20_whitelist(deps, env, info, add, remove),
+        ExecuteMsg::ListForSale {
+            seller, token_id, amount, unit_price
+        } => execute_list_for_sale(deps, env, info, seller, token_id, amount, unit_price),
+        ExecuteMsg::Buy { listing_id, amount } => execute_buy(deps, env, info, listing_id, amount),
+        ExecuteMsg::Delist { listing_id } => execute_delist(deps, env, info, listing_id),
+        ExecuteMsg::MakeOffer {
+            owner, token_id, amount, expires
+        } => execute_make_offer(deps, env, info, owner, token_id, amount, expires),
+        ExecuteMsg::AcceptOffer { offer_id } => execute_accept_offer(deps, env, info, offer_id),
+        ExecuteMsg::WithdrawOffer { offer_id } => execute_withdraw_offer(deps, env, info, offer_id),
+        ExecuteMsg::MakeCollectionOffer {
+            scope, unit_price, quantity, expires
+        } => execute_make_collection_offer(deps, env, info, scope, unit_price, quantity, expires),
+        ExecuteMsg::FillCollectionOffer {
+            offer_id, owner, token_id, amount
+        } => execute_fill_collection_offer(deps, env, info, offer_id, owner, token_id, amount),
+        ExecuteMsg::WithdrawCollectionOffer { offer_id } => execute_withdraw_collection_offer(deps, env, info, offer_id),
+        ExecuteMsg::UpdateProtocolFee {
+            protocol_fee_bps, treasury
+        } => execute_update_protocol_fee(deps, env, info, protocol_fee_bps, treasury),
+        ExecuteMsg::Receive(msg) => match msg.into_received()? {
+            TokenReceived::Cw1155(msg) => execute_receive(deps, env, info, msg),
+            TokenReceived::Cw20(msg) => execute_receive_cw20(deps, env, info, msg),
+        },
+        ExecuteMsg::BatchReceive(msg) => execute_batch_receive(deps, env, info, msg),
+    }
+}
+
+/// Only bids, purchases and offers take funds, the counter is left as it was
+fn is_payable(msg: &ExecuteMsg) -> bool {
+    matches!(
+        msg,
+        ExecuteMsg::Increment {}
+            | ExecuteMsg::Reset { .. }
+            | ExecuteMsg::Bid { .. }
+            | ExecuteMsg::CommitBid { .. }
+            | ExecuteMsg::BidUnits { .. }
+            | ExecuteMsg::Buy { .. }
+            | ExecuteMsg::MakeOffer { .. }
+            | ExecuteMsg::MakeCollectionOffer { .. }
+    )
+}
+
+/// Settle a pending sale once its `SendFrom` to an external collection returns,
+/// paying the seller when it went through and refunding the buyer when it failed.
+/// Failed returns to the seller are only reported.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    mut deps: DepsMut,
+    env: Env,
+    msg: Reply,
+) -> Result<Response, ContractError> {
+    let mut rsp = Response::new().add_attribute("method", "reply");
+    let pending = match PENDING_SALES.may_load(deps.storage, msg.id.into())? {
+        None => {
+            if let ContractResult::Err(err) = msg.result {
+                rsp = rsp.add_attribute("transfer_failed", err);
+            }
+            return Ok(rsp);
+        }
+        Some(pending) => pending,
+    };
+    PENDING_SALES.remove(deps.storage, msg.id.into());
+    let auction = auctions().load(deps.storage, pending.auction_id.into())?;
+    rsp = rsp.add_attribute("auction_id", pending.auction_id.to_string());
+    match msg.result {
+        ContractResult::Ok(_) => pay_seller(&mut deps, &env, &Sale::from(&auction), &pending.price, &mut rsp)?,
+        ContractResult::Err(err) => {
+            rsp = rsp.add_attribute("transfer_failed", err);
+            credit_claimable(&mut deps, &pending.buyer, &pending.price, auction.cw20)?;
+        }
     }
+    Ok(rsp)
 }
 
 pub fn try_increment(deps: DepsMut) -> Result<Response, ContractError> {
@@ -88,22 +226,105 @@ pub fn try_reset(deps: DepsMut, info: MessageInfo, count: i32) -> Result<Respons
     Ok(Response::new().add_attribute("method", "reset"))
 }
 
-/// Check if such auction exist
-/// Check if user has sufficient tokens
-/// Create auction
-pub fn execute_create_auction(
+/// Check owner
+/// Remove then add native denoms, keeping each once
+pub fn execute_update_accepted_denoms(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    config.accepted_denoms.retain(|denom| !remove.contains(denom));
+    for denom in add {
+        if !config.accepted_denoms.contains(&denom) {
+            config.accepted_denoms.push(denom);
+        }
+    }
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new()
+        .add_attribute("method", "execute_update_accepted_denoms")
+        .add_attribute("accepted_denoms", config.accepted_denoms.join(",")))
+}
+
+/// Check owner
+/// Remove then add CW20 tokens, keeping each once
+pub fn execute_update_cw20_whitelist(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    config.cw20_whitelist.retain(|token| !remove.iter().any(|removed| token == removed));
+    for token in add {
+        let token = deps.api.addr_validate(&token)?;
+        if !config.cw20_whitelist.contains(&token) {
+            config.cw20_whitelist.push(token);
+        }
+    }
+    CONFIG.save(deps.storage, &config)?;
+    let whitelist: Vec<&str> = config.cw20_whitelist.iter().map(Addr::as_str).collect();
+    Ok(Response::new()
+        .add_attribute("method", "execute_update_cw20_whitelist")
+        .add_attribute("cw20_whitelist", whitelist.join(",")))
+}
+
+/// Check owner
+/// Replace fee and treasury for later settlements
+pub fn execute_update_protocol_fee(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
+    protocol_fee_bps: u64,
+    treasury: String,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.as_ref(), &info.sender)?;
+    validate_protocol_fee(protocol_fee_bps)?;
+    let treasury = deps.api.addr_validate(&treasury)?;
+    CONFIG.update(deps.storage, |config| -> StdResult<_> {
+        Ok(Config { protocol_fee_bps, treasury: treasury.clone(), ..config })
+    })?;
+    Ok(Response::new()
+        .add_attribute("method", "execute_update_protocol_fee")
+        .add_attribute("protocol_fee_bps", protocol_fee_bps.to_string())
+        .add_attribute("treasury", treasury))
+}
+
+fn validate_protocol_fee(protocol_fee_bps: u64) -> Result<(), ContractError> {
+    if protocol_fee_bps > 10_000 {
+        return Err(ContractError::InvalidProtocolFee {});
+    }
+    Ok(())
+}
+
+fn assert_owner(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if *sender != STATE.load(deps.storage)?.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Check if seller has sufficient tokens
+/// Move tokens into escrow
+/// Create auction under a new id
+pub fn execute_create_auction(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     token_id: TokenId,
     amount: Uint128,
-    price: Coin,
     seller: String,
-    bidding_close: Expiration,
+    terms: AuctionTerms,
 ) -> Result<Response, ContractError> {
     // Fetch Address and Balance
     let seller_addr = deps.api.addr_validate(&seller)?;
-    let balance = BALANCES.may_load(deps.storage, (&info.sender, &token_id))?;
+    guard_can_approve(deps.as_ref(), &env, &seller_addr, &info.sender)?;
+    let balance = BALANCES.may_load(deps.storage, (&seller_addr, &token_id))?;
     match balance {
         None => { return Err(ContractError::InsufficientNftBalance {}); }
         Some(balance_val) => {
@@ -113,470 +334,3989 @@ pub fn execute_create_auction(
             }
         }
     }
-    // Fetch Auction
-    let auction = AUCTIONS.may_load(deps.storage, (&seller_addr, &token_id))?;
-    match auction {
-        None => {
-            // Create New Auction
-            let new_auction = Auction {
-                amount: amount,
-                price: price,
-                highest_bidder: seller_addr.clone(),
-                bidding_close: bidding_close,
-            };
-            AUCTIONS.save(
-                deps.storage,
-                (&seller_addr, &token_id),
-                &new_auction
-            )?;
+    // Create New Auction
+    let collection = env.contract.address.clone();
+    let auction_id = save_new_auction(&mut deps, &collection, &seller_addr, &token_id, amount, terms)?;
+    let mut rsp = Response::new()
+        .add_attribute("method", "execute_create_auction")
+        .add_attribute("auction_id", auction_id.to_string())
+        .set_data(to_binary(&auction_id)?);
+    escrow_lock(&mut deps, &env, &seller_addr, &token_id, amount, &mut rsp)?;
+    Ok(rsp)
+}
+
+/// Tokens arrive from `info.sender`, either this contract or an external cw1155
+/// Decode the embedded payload
+/// Create auction with the sender of the tokens as seller
+pub fn execute_receive(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw1155ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let seller = wrapper.from.unwrap_or(wrapper.operator);
+    let seller_addr = deps.api.addr_validate(&seller)?;
+    let msg: ReceiveMsg = from_binary(&wrapper.msg)?;
+    let auction_id = match msg {
+        ReceiveMsg::CreateAuction {
+            reserve_price, bidding_start, bidding_close, min_increment, anti_snipe, buy_now_price, kind, payees
+        } => {
+            let terms = AuctionTerms { reserve_price, bidding_start, bidding_close, min_increment, anti_snipe, buy_now_price, kind, payees };
+            receive_auction(&mut deps, &env, &info.sender, &seller_addr, &wrapper.token_id, wrapper.amount, terms)?
+        }
+    };
+    Ok(Response::new()
+        .add_attribute("method", "execute_receive")
+        .add_attribute("collection", info.sender)
+        .add_attribute("token_id", wrapper.token_id)
+        .add_attribute("auction_id", auction_id.to_string())
+        .set_data(to_binary(&auction_id)?))
+}
+
+/// CW20 tokens arrive from `info.sender`, the token contract, without native coins
+/// Decode the embedded payload
+/// Bid with the sent tokens on behalf of the sender of the tokens
+pub fn execute_receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    assert_no_funds(&info.funds)?;
+    // Native denoms can never be a contract address, so the denom checks
+    // of every bid keep native coins and CW20 tokens apart.
+    let bid_info = MessageInfo {
+        sender: deps.api.addr_validate(&wrapper.sender)?,
+        funds: vec![coin(wrapper.amount.u128(), info.sender.as_str())],
+    };
+    match from_binary(&wrapper.msg)? {
+        Cw20HookMsg::Bid { auction_id } => execute_bid(deps, env, bid_info, auction_id),
+        Cw20HookMsg::CommitBid { auction_id, commitment } => execute_commit_bid(deps, env, bid_info, auction_id, commitment),
+        Cw20HookMsg::BidUnits {
+            auction_id, quantity, unit_price
+        } => execute_bid_units(deps, env, bid_info, auction_id, quantity, unit_price),
+        Cw20HookMsg::Buy { listing_id, amount } => execute_buy(deps, env, bid_info, listing_id, amount),
+        Cw20HookMsg::MakeOffer {
+            owner, token_id, amount, expires
+        } => execute_make_offer(deps, env, bid_info, owner, token_id, amount, expires),
+        Cw20HookMsg::MakeCollectionOffer {
+            scope, unit_price, quantity, expires
+        } => execute_make_collection_offer(deps, env, bid_info, scope, unit_price, quantity, expires),
+    }
+}
+
+/// Same as `execute_receive`, once for every token_id in the batch
+pub fn execute_batch_receive(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw1155BatchReceiveMsg,
+) -> Result<Response, ContractError> {
+    let seller = wrapper.from.unwrap_or(wrapper.operator);
+    let seller_addr = deps.api.addr_validate(&seller)?;
+    let msg: ReceiveMsg = from_binary(&wrapper.msg)?;
+    let mut auction_ids = vec![];
+    match msg {
+        ReceiveMsg::CreateAuction {
+            reserve_price, bidding_start, bidding_close, min_increment, anti_snipe, buy_now_price, kind, payees
+        } => {
+            let terms = AuctionTerms { reserve_price, bidding_start, bidding_close, min_increment, anti_snipe, buy_now_price, kind, payees };
+            for (token_id, amount) in wrapper.batch.iter() {
+                auction_ids.push(receive_auction(&mut deps, &env, &info.sender, &seller_addr, token_id, *amount, terms.clone())?);
+            }
+        }
+    }
+    let mut rsp = Response::new()
+        .add_attribute("method", "execute_batch_receive")
+        .add_attribute("collection", info.sender);
+    for auction_id in auction_ids.iter() {
+        rsp = rsp.add_attribute("auction_id", auction_id.to_string());
+    }
+    Ok(rsp.set_data(to_binary(&auction_ids)?))
+}
+
+/// Create an auction for tokens already sent to the contract,
+/// tokens of this contract are recorded in escrow for the seller.
+fn receive_auction(
+    deps: &mut DepsMut,
+    env: &Env,
+    collection: &Addr,
+    seller_addr: &Addr,
+    token_id: &str,
+    amount: Uint128,
+    terms: AuctionTerms,
+) -> Result<u64, ContractError> {
+    let auction_id = save_new_auction(deps, collection, seller_addr, token_id, amount, terms)?;
+    if *collection == env.contract.address {
+        escrow_add(deps, seller_addr, token_id, amount)?;
+    }
+    Ok(auction_id)
+}
+
+/// Validate terms and fill in contract defaults
+/// Save under the next auction id
+fn save_new_auction(
+    deps: &mut DepsMut,
+    collection: &Addr,
+    seller_addr: &Addr,
+    token_id: &str,
+    amount: Uint128,
+    terms: AuctionTerms,
+) -> Result<u64, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let cw20 = accepted_denom(&config, &terms.reserve_price.denom)?;
+    let min_increment = terms.min_increment.unwrap_or(config.default_min_increment);
+    validate_bid_increment(&min_increment)?;
+    // Auctions that never close could never settle
+    if terms.bidding_close == (Expiration::Never {}) {
+        return Err(ContractError::InvalidAuction { reason: "bidding_close must be a height or a time".to_string() });
+    }
+    if let Some(bidding_start) = &terms.bidding_start {
+        validate_bidding_start(bidding_start, &terms.bidding_close)?;
+    }
+    if let Some(anti_snipe) = &terms.anti_snipe {
+        validate_anti_snipe(&terms.bidding_close, anti_snipe)?;
+    }
+    if let Some(buy_now_price) = &terms.buy_now_price {
+        if buy_now_price.denom != terms.reserve_price.denom || buy_now_price.amount < terms.reserve_price.amount {
+            return Err(ContractError::InvalidAuction { reason: "buy now price must be at least the reserve price".to_string() });
+        }
+    }
+    let kind = terms.kind.unwrap_or_default();
+    if kind != (AuctionKind::English {}) && (terms.anti_snipe.is_some() || terms.buy_now_price.is_some()) {
+        return Err(ContractError::InvalidAuction { reason: "only english auctions have anti-snipe or buy now price".to_string() });
+    }
+    match &kind {
+        AuctionKind::English {} | AuctionKind::MultiUnit {} => {}
+        AuctionKind::Dutch(dutch) => validate_dutch(&terms.reserve_price, dutch, &terms.bidding_close)?,
+        AuctionKind::Sealed(sealed) => {
+            if !same_finite_unit(&sealed.reveal_close, &terms.bidding_close) {
+                return Err(ContractError::InvalidAuction { reason: "reveal_close must match the unit of bidding_close".to_string() });
+            }
+            if sealed.reveal_close.partial_cmp(&terms.bidding_close) != Some(Ordering::Greater) {
+                return Err(ContractError::InvalidAuction { reason: "reveal must close after bidding".to_string() });
+            }
+        }
+    }
+    let payees = match terms.payees {
+        Some(payees) => validate_payees(deps, payees)?,
+        None => vec![],
+    };
+    let auction_id = AUCTION_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    AUCTION_COUNT.save(deps.storage, &auction_id)?;
+    let auction = Auction {
+        seller: seller_addr.clone(),
+        collection: collection.clone(),
+        token_id: token_id.to_string(),
+        amount,
+        price: terms.reserve_price.clone(),
+        reserve_price: terms.reserve_price,
+        highest_bidder: None,
+        bidding_start: terms.bidding_start,
+        bidding_close: terms.bidding_close,
+        min_increment,
+        anti_snipe: terms.anti_snipe,
+        buy_now_price: terms.buy_now_price,
+        kind,
+        cw20,
+        payees,
+        status: AuctionStatus::Active {},
+    };
+    auctions().save(deps.storage, auction_id.into(), &auction)?;
+    Ok(auction_id)
+}
+
+fn validate_bid_increment(increment: &BidIncrement) -> Result<(), ContractError> {
+    match increment {
+        BidIncrement::BasisPoints { bps } if *bps > 10_000 => Err(ContractError::InvalidBidIncrement {}),
+        _ => Ok(()),
+    }
+}
+
+/// Whether `denom` is a whitelisted CW20 token, failing for denoms not accepted at all
+fn accepted_denom(config: &Config, denom: &str) -> Result<bool, ContractError> {
+    if config.cw20_whitelist.iter().any(|token| token.as_str() == denom) {
+        return Ok(true);
+    }
+    if !config.accepted_denoms.iter().any(|accepted| accepted == denom) {
+        return Err(ContractError::DenomNotAccepted { denom: denom.to_string() });
+    }
+    Ok(false)
+}
+
+/// Every payee takes a share, the shares make up the whole
+fn validate_payees(deps: &DepsMut, payees: Vec<PayeeMsg>) -> Result<Vec<Payee>, ContractError> {
+    let mut total = 0u64;
+    let mut validated = vec![];
+    for payee in payees {
+        if payee.share_bps == 0 {
+            return Err(ContractError::InvalidAuction { reason: "payee shares must not be zero".to_string() });
         }
-        Some(_auction_val) => { return Err(ContractError::InvalidAuction {}); }
+        total = total.saturating_add(payee.share_bps);
+        validated.push(Payee { address: deps.api.addr_validate(&payee.address)?, share_bps: payee.share_bps });
+    }
+    if total != 10_000 {
+        return Err(ContractError::InvalidAuction { reason: "payee shares must sum to 10000".to_string() });
+    }
+    Ok(validated)
+}
+
+/// Bidding must open before it closes, in the same unit
+fn validate_bidding_start(bidding_start: &Expiration, bidding_close: &Expiration) -> Result<(), ContractError> {
+    if !same_finite_unit(bidding_start, bidding_close) {
+        return Err(ContractError::InvalidAuction { reason: "bidding_start must match the unit of bidding_close".to_string() });
+    }
+    if bidding_start.partial_cmp(bidding_close) != Some(Ordering::Less) {
+        return Err(ContractError::InvalidAuction { reason: "bidding must start before it closes".to_string() });
+    }
+    Ok(())
+}
+
+/// Both are heights or both are times, `Never` compares after anything
+fn same_finite_unit(a: &Expiration, b: &Expiration) -> bool {
+    matches!(
+        (a, b),
+        (Expiration::AtHeight(_), Expiration::AtHeight(_)) | (Expiration::AtTime(_), Expiration::AtTime(_))
+    )
+}
+
+/// Extensions must use the unit of bidding_close and cap at or after it
+fn validate_anti_snipe(bidding_close: &Expiration, anti_snipe: &AntiSnipe) -> Result<(), ContractError> {
+    let same_unit = |duration: &Duration| matches!(
+        (bidding_close, duration),
+        (Expiration::AtHeight(_), Duration::Height(_)) | (Expiration::AtTime(_), Duration::Time(_))
+    );
+    if !same_unit(&anti_snipe.window) || !same_unit(&anti_snipe.extend_by) {
+        return Err(ContractError::InvalidAuction { reason: "anti-snipe durations must match bidding_close".to_string() });
+    }
+    if !same_finite_unit(&anti_snipe.max_close, bidding_close) {
+        return Err(ContractError::InvalidAuction { reason: "anti-snipe cap must match the unit of bidding_close".to_string() });
+    }
+    match anti_snipe.max_close.partial_cmp(bidding_close) {
+        Some(Ordering::Greater) | Some(Ordering::Equal) => Ok(()),
+        _ => Err(ContractError::InvalidAuction { reason: "anti-snipe cap must not be before bidding_close".to_string() }),
+    }
+}
+
+/// Price falls from above the reserve over a window in a single unit, done by bidding_close
+fn validate_dutch(reserve_price: &Coin, dutch: &DutchAuction, bidding_close: &Expiration) -> Result<(), ContractError> {
+    if dutch.start_price.denom != reserve_price.denom || dutch.start_price.amount < reserve_price.amount {
+        return Err(ContractError::InvalidAuction { reason: "start price must be at least the reserve price".to_string() });
+    }
+    if !same_finite_unit(&dutch.decay_start, &dutch.decay_end) {
+        return Err(ContractError::InvalidAuction { reason: "decay must start and end in the same unit".to_string() });
+    }
+    if dutch.decay_start.partial_cmp(&dutch.decay_end) != Some(Ordering::Less) {
+        return Err(ContractError::InvalidAuction { reason: "decay must start before it ends".to_string() });
+    }
+    if !same_finite_unit(&dutch.decay_end, bidding_close) || dutch.decay_end > *bidding_close {
+        return Err(ContractError::InvalidAuction { reason: "decay must end by bidding_close".to_string() });
+    }
+    if dutch.decay == (PriceDecay::Stepped { steps: 0 }) {
+        return Err(ContractError::InvalidAuction { reason: "stepped decay needs at least one step".to_string() });
+    }
+    Ok(())
+}
+
+/// Bids are only taken while the auction is active
+fn assert_taking_bids(auction: &Auction, block: &BlockInfo) -> Result<(), ContractError> {
+    match auction.status_at(block) {
+        AuctionStatus::Active {} => Ok(()),
+        AuctionStatus::Scheduled {} => Err(ContractError::AuctionNotStarted {}),
+        AuctionStatus::Ended {} => Err(ContractError::AuctionEnded {}),
+        status => Err(ContractError::InvalidAuction { reason: format!("auction is {}", status) }),
     }
-    Ok(Response::new().add_attribute("method", "execute_create_auction"))
 }
 
 /// Get Auction Highest Bidder
-/// Reject if price lower than highest
+/// Reject if price lower than reserve or highest
 /// Set new price and owner
-/// Return money to highest bidder
+/// Credit money back to previous highest bidder
+/// Settle right away when the buy now price is met
 /// - Send Money Function
 /// - Validate Money function
 pub fn execute_bid(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    token_id: TokenId,
-    seller: String,
+    auction_id: u64,
 ) -> Result<Response, ContractError> {
-    // Fetch auction and seller address
-    let seller_addr = deps.api.addr_validate(&seller)?;
-    let auction = AUCTIONS.may_load(deps.storage, (&seller_addr, &token_id))?;
-    
+    let auction = auctions().may_load(deps.storage, auction_id.into())?;
+
     match auction {
-        None => { return Err(ContractError::InvalidAuction {}); }
+        None => Err(ContractError::InvalidAuction { reason: "auction does not exist".to_string() }),
         Some(auction_val) => {
             // Bidding Not Expired
-            if auction_val.bidding_close.is_expired(&env.block) {
-                return Err(ContractError::AuctionEnded {});
+            assert_taking_bids(&auction_val, &env.block)?;
+            match auction_val.kind {
+                AuctionKind::English {} | AuctionKind::Dutch(_) => {}
+                AuctionKind::Sealed(_) => {
+                    return Err(ContractError::InvalidAuction { reason: "sealed-bid auctions take commitments".to_string() });
+                }
+                AuctionKind::MultiUnit {} => {
+                    return Err(ContractError::InvalidAuction { reason: "multi-unit auctions take unit bids".to_string() });
+                }
+            }
+            // Sufficient coins, the first bid only has to meet the reserve
+            let required = auction_val.next_min_bid(&env.block)?;
+            let sent_coin = assert_sent_sufficient_coin(&info.funds, &required)?;
+            if let AuctionKind::Dutch(_) = auction_val.kind {
+                return execute_dutch_buy(deps, env, info.sender, auction_id, auction_val, sent_coin, required);
             }
-            // Sufficient coins
-            let sent_coin = assert_sent_sufficient_coin(&info.funds, Some(auction_val.price.clone()))?;
-            match sent_coin {
-                None => { return Err(ContractError::InsufficientFundsSend {}); }
-                Some(sent_coin_val) => {
-                    let new_auction = Auction {
-                        amount: auction_val.amount.clone(),
-                        bidding_close: auction_val.bidding_close.clone(),
-                        price: sent_coin_val,
-                        highest_bidder: info.sender,
-                    };
-                    AUCTIONS.save(
-                        deps.storage,
-                        (&seller_addr, &token_id),
-                        &new_auction
-                    )?;
+            // Bids past the buy now price only pay it, credit the rest back
+            let price = match &auction_val.buy_now_price {
+                Some(buy_now_price) if sent_coin.amount > buy_now_price.amount => {
+                    let overpaid = coin((sent_coin.amount - buy_now_price.amount).u128(), &buy_now_price.denom);
+                    credit_claimable(&mut deps, &info.sender, &overpaid, auction_val.cw20)?;
+                    buy_now_price.clone()
                 }
+                _ => sent_coin,
+            };
+            record_bid(&mut deps, &env, auction_id, &info.sender, price.clone(), None)?;
+            let mut new_auction = Auction {
+                price,
+                highest_bidder: Some(info.sender),
+                ..auction_val.clone()
+            };
+            let mut rsp = Response::new()
+                .add_attribute("Bidding", &auction_val.token_id)
+                .add_attribute("auction_id", auction_id.to_string());
+            // Refund the previous highest bidder
+            if let Some(previous_bidder) = &auction_val.highest_bidder {
+                credit_claimable(&mut deps, previous_bidder, &auction_val.price, auction_val.cw20)?;
             }
-            Ok(Response::new()
-                .add_attribute("Bidding", &token_id.to_string())
-                .add_message(CosmosMsg::Bank(BankMsg::Send {
-                    to_address: auction_val.highest_bidder.to_string(),
-                    amount: vec![auction_val.price.clone()],
-                })))
+            if new_auction.buy_now_reached() {
+                rsp = rsp.add_attribute("buy_now", new_auction.price.to_string());
+                settle_auction(&mut deps, &env, auction_id, &new_auction, &mut rsp)?;
+                return Ok(rsp);
+            }
+            // Late bids push bidding_close out
+            if let Some(new_close) = new_auction.extend_close(&env.block)? {
+                rsp = rsp.add_attribute("bidding_close_extended", new_close.to_string());
+            }
+            auctions().save(deps.storage, auction_id.into(), &new_auction)?;
+            Ok(rsp)
         }
     }
 }
 
+/// First buyer at the live price wins
+/// Credit overpayment back to buyer
+/// Settle Auction
+fn execute_dutch_buy(
+    mut deps: DepsMut,
+    env: Env,
+    buyer: Addr,
+    auction_id: u64,
+    auction: Auction,
+    sent_coin: Coin,
+    price: Coin,
+) -> Result<Response, ContractError> {
+    let mut rsp = Response::new()
+        .add_attribute("Bidding", &auction.token_id)
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("dutch_price", price.to_string());
+    if sent_coin.amount > price.amount {
+        let overpaid = coin((sent_coin.amount - price.amount).u128(), &price.denom);
+        credit_claimable(&mut deps, &buyer, &overpaid, auction.cw20)?;
+    }
+    record_bid(&mut deps, &env, auction_id, &buyer, price.clone(), None)?;
+    let sold = Auction {
+        price,
+        highest_bidder: Some(buyer),
+        ..auction
+    };
+    settle_auction(&mut deps, &env, auction_id, &sold, &mut rsp)?;
+    Ok(rsp)
+}
+
 /// Check if Auction expired
-/// Send NFT to highest bidder
-/// Send Bid amount to user
+/// Settle Auction
+/// Anyone may settle once bidding has closed
 pub fn execute_auction_close(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
-    info: MessageInfo,
-    token_id: String,
-    seller: String,
+    _info: MessageInfo,
+    auction_id: u64,
 ) -> Result<Response, ContractError> {
-    // Fetch Address and Auction
-    let seller_addr = deps.api.addr_validate(&seller)?;
-    let auction = AUCTIONS.may_load(deps.storage, (&seller_addr, &token_id))?;
+    let auction = auctions().may_load(deps.storage, auction_id.into())?;
     match auction {
-        None => { return Err(ContractError::InvalidAuction {}); }
+        None => Err(ContractError::InvalidAuction { reason: "auction does not exist".to_string() }),
         Some(auction_val) => {
-            // Send NFT to Highest Bidder
-            execute_send_from(
-                deps,
-                env,
-                info,
-                seller_addr.to_string(),
-                auction_val.highest_bidder.clone().to_string(),
-                token_id.clone(),
-                auction_val.amount.clone(),
-                None,
-            )?;
-            // Send Money to Auction Seller
-            Ok(Response::new()
-                .add_attribute("Bidding", &token_id.to_string())
-                .add_message(CosmosMsg::Bank(BankMsg::Send {
-                    to_address: seller_addr.to_string(),
-                    amount: vec![auction_val.price.clone()],
-                })))
+            // Bidding Expired and not settled yet
+            if auction_val.status != (AuctionStatus::Active {}) {
+                return Err(ContractError::InvalidAuction { reason: format!("auction is {}", auction_val.status) });
+            }
+            if !auction_val.settle_after().is_expired(&env.block) {
+                return Err(ContractError::AuctionNotEnded {});
+            }
+            let mut rsp = Response::new()
+                .add_attribute("Bidding", &auction_val.token_id)
+                .add_attribute("auction_id", auction_id.to_string());
+            let mut auction_val = auction_val;
+            match auction_val.kind.clone() {
+                AuctionKind::Sealed(sealed) => {
+                    settle_commitments(&mut deps, auction_id, &mut auction_val, &sealed, &mut rsp)?;
+                }
+                AuctionKind::MultiUnit {} => {
+                    settle_unit_bids(&mut deps, &env, auction_id, &auction_val, &mut rsp)?;
+                    return Ok(rsp);
+                }
+                _ => {}
+            }
+            settle_auction(&mut deps, &env, auction_id, &auction_val, &mut rsp)?;
+            Ok(rsp)
         }
     }
 }
 
+/// Set the clearing price of a sealed-bid auction
+/// Credit deposits back, less the price for the winner
+/// Forfeit unrevealed and invalid deposits to seller if configured
+fn settle_commitments(
+    deps: &mut DepsMut,
+    auction_id: u64,
+    auction: &mut Auction,
+    sealed: &SealedAuction,
+    rsp: &mut Response,
+) -> Result<(), ContractError> {
+    let commitments = COMMITMENTS
+        .prefix(auction_id.into())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, commitment)| commitment))
+        .collect::<StdResult<Vec<Commitment>>>()?;
 
-/// When from is None: mint new coins
-/// When to is None: burn coins
-/// When both are None: no token balance is changed, pointless but valid
-/// Make sure permissions are checked before calling this.
-fn execute_transfer_inner<'a>(
-    deps: &'a mut DepsMut,
-    from: Option<&'a Addr>,
-    to: Option<&'a Addr>,
-    token_id: &'a str,
-    amount: Uint128,
-) -> Result<TransferEvent<'a>, ContractError> {
-    if let Some(from_addr) = from {
-        BALANCES.update(
-            deps.storage,
-            (from_addr, token_id),
-            |balance: Option<Uint128>| -> StdResult<_> {
-                Ok(balance.unwrap_or_default().checked_sub(amount)?)
-            },
-        )?;
+    // Second highest valid bid, the winner is already tracked on the auction
+    let second_price = commitments
+        .iter()
+        .filter(|commitment| Some(&commitment.bidder) != auction.highest_bidder.as_ref())
+        .filter_map(|commitment| match commitment.status {
+            CommitmentStatus::Revealed { amount } => Some(amount),
+            _ => None,
+        })
+        .max();
+    if auction.highest_bidder.is_some() {
+        if let SealedPricing::SecondPrice {} = sealed.pricing {
+            let amount = std::cmp::max(second_price.unwrap_or_default(), auction.reserve_price.amount);
+            auction.price = coin(amount.u128(), &auction.price.denom);
+        }
     }
 
-    if let Some(to_addr) = to {
-        BALANCES.update(
-            deps.storage,
-            (to_addr, token_id),
-            |balance: Option<Uint128>| -> StdResult<_> {
-                Ok(balance.unwrap_or_default().checked_add(amount)?)
+    let mut forfeited = Uint128::zero();
+    for commitment in commitments {
+        COMMITMENTS.remove(deps.storage, (auction_id.into(), &commitment.bidder));
+        let refund = match commitment.status {
+            CommitmentStatus::Revealed { .. } if Some(&commitment.bidder) == auction.highest_bidder.as_ref() => {
+                commitment.deposit.amount.checked_sub(auction.price.amount)?
+            }
+            CommitmentStatus::Revealed { .. } => commitment.deposit.amount,
+            _ => match sealed.penalty {
+                CommitmentPenalty::Refund {} => commitment.deposit.amount,
+                CommitmentPenalty::Forfeit {} => {
+                    forfeited = forfeited.checked_add(commitment.deposit.amount)?;
+                    Uint128::zero()
+                }
             },
-        )?;
+        };
+        credit_claimable(deps, &commitment.bidder, &coin(refund.u128(), &commitment.deposit.denom), auction.cw20)?;
     }
+    if !forfeited.is_zero() {
+        rsp.attributes.push(attr("forfeited", forfeited));
+        credit_claimable(deps, &auction.seller, &coin(forfeited.u128(), &auction.reserve_price.denom), auction.cw20)?;
+    }
+    Ok(())
+}
 
-    Ok(TransferEvent {
-        from: from.map(|x| x.as_ref()),
-        to: to.map(|x| x.as_ref()),
-        token_id,
-        amount,
-    })
+/// Mark Auction settled so it cannot be settled twice
+/// Fill units from the highest unit price down, earlier bids first on ties
+/// Charge every winner the unit price of the lowest winning bid
+/// Return unsold units to seller, credit back the rest of every deposit
+fn settle_unit_bids(
+    deps: &mut DepsMut,
+    env: &Env,
+    auction_id: u64,
+    auction: &Auction,
+    rsp: &mut Response,
+) -> Result<(), ContractError> {
+    let mut bids = UNIT_BIDS
+        .prefix(auction_id.into())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, bid)| bid))
+        .collect::<StdResult<Vec<UnitBid>>>()?;
+    bids.sort_by(|a, b| b.unit_price.cmp(&a.unit_price).then(a.height.cmp(&b.height)));
+
+    let mut remaining = auction.amount;
+    let mut fills = vec![];
+    for bid in bids.iter() {
+        let fill = std::cmp::min(bid.quantity, remaining);
+        remaining = remaining.checked_sub(fill)?;
+        fills.push(fill);
+    }
+    let clearing_price = bids
+        .iter()
+        .zip(fills.iter())
+        .filter(|(_, fill)| !fill.is_zero())
+        .map(|(bid, _)| bid.unit_price)
+        .min()
+        .unwrap_or_default();
+    rsp.attributes.push(attr("clearing_price", clearing_price));
+
+    let status = if remaining == auction.amount { AuctionStatus::NoSale {} } else { AuctionStatus::Settled {} };
+    auctions().save(deps.storage, auction_id.into(), &Auction { status, ..auction.clone() })?;
+    for (bid, fill) in bids.iter().zip(fills) {
+        UNIT_BIDS.remove(deps.storage, (auction_id.into(), &bid.bidder));
+        let cost = fill.checked_mul(clearing_price)?;
+        if !fill.is_zero() {
+            let price = coin(cost.u128(), &auction.reserve_price.denom);
+            auction_sell_units(deps, env, auction_id, auction, &bid.bidder, fill, &price, rsp)?;
+        }
+        let refund = bid.deposit.amount.checked_sub(cost)?;
+        credit_claimable(deps, &bid.bidder, &coin(refund.u128(), &bid.deposit.denom), auction.cw20)?;
+    }
+    if !remaining.is_zero() {
+        auction_transfer_units(deps, env, auction, &auction.seller, remaining, rsp)?;
+    }
+    Ok(())
 }
 
-/// returns true iff the sender can execute approve or reject on the contract
-fn check_can_approve(deps: Deps, env: &Env, owner: &Addr, operator: &Addr) -> StdResult<bool> {
-    // owner can approve
-    if owner == operator {
-        return Ok(true);
+/// Mark Auction settled so it cannot be settled twice
+/// Send NFT from escrow to highest bidder and pay the seller, back to the seller without bids
+fn settle_auction(
+    deps: &mut DepsMut,
+    env: &Env,
+    auction_id: u64,
+    auction: &Auction,
+    rsp: &mut Response,
+) -> Result<(), ContractError> {
+    let status = match auction.highest_bidder {
+        None => AuctionStatus::NoSale {},
+        Some(_) => AuctionStatus::Settled {},
+    };
+    auctions().save(deps.storage, auction_id.into(), &Auction { status, ..auction.clone() })?;
+    match &auction.highest_bidder {
+        // No bids, return NFT to Auction Seller
+        None => auction_transfer(deps, env, auction, &auction.seller, rsp),
+        Some(highest_bidder) => {
+            auction_sell_units(deps, env, auction_id, auction, highest_bidder, auction.amount, &auction.price, rsp)
+        }
     }
-    // operator can approve
-    let op = APPROVES.may_load(deps.storage, (&owner, &operator))?;
-    Ok(match op {
-        Some(ex) => !ex.is_expired(&env.block),
-        None => false,
-    })
 }
 
-fn guard_can_approve(
-    deps: Deps,
+/// Deduct the protocol fee for the treasury, rounded down
+/// Deduct the royalty of tokens of this contract for their creator, rounded down
+/// and capped at what the fee leaves
+/// Credit the rest of the sale proceeds to the payees, or the seller without any
+fn pay_seller(
+    deps: &mut DepsMut,
     env: &Env,
-    owner: &Addr,
-    operator: &Addr,
+    sale: &Sale,
+    proceeds: &Coin,
+    rsp: &mut Response,
 ) -> Result<(), ContractError> {
-    if !check_can_approve(deps, env, owner, operator)? {
-        Err(ContractError::Unauthorized {})
-    } else {
-        Ok(())
+    if proceeds.amount.is_zero() {
+        return Ok(());
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let fee = proceeds.amount.multiply_ratio(config.protocol_fee_bps, 10_000u64);
+    let mut seller_proceeds = proceeds.amount.checked_sub(fee)?;
+    rsp.attributes.push(attr("protocol_fee", fee));
+    credit_claimable(deps, &config.treasury, &coin(fee.u128(), &proceeds.denom), sale.cw20)?;
+    if *sale.collection == env.contract.address {
+        if let Some(royalty) = ROYALTIES.may_load(deps.storage, sale.token_id)? {
+            let royalty_amount = std::cmp::min(proceeds.amount.multiply_ratio(royalty.bps, 10_000u64), seller_proceeds);
+            seller_proceeds = seller_proceeds.checked_sub(royalty_amount)?;
+            rsp.attributes.push(attr("royalty", royalty_amount));
+            rsp.attributes.push(attr("royalty_recipient", &royalty.recipient));
+            credit_claimable(deps, &royalty.recipient, &coin(royalty_amount.u128(), &proceeds.denom), sale.cw20)?;
+        }
     }
+    rsp.attributes.push(attr("seller_proceeds", seller_proceeds));
+    // Shares round down, the dust goes to the first payee
+    let mut shares: Vec<(&Addr, Uint128)> = sale.payees
+        .iter()
+        .map(|payee| (&payee.address, seller_proceeds.multiply_ratio(payee.share_bps, 10_000u64)))
+        .collect();
+    if shares.is_empty() {
+        shares.push((sale.seller, Uint128::zero()));
+    }
+    let paid = shares.iter().try_fold(Uint128::zero(), |total, (_, share)| total.checked_add(*share))?;
+    shares[0].1 = shares[0].1.checked_add(seller_proceeds.checked_sub(paid)?)?;
+    for (payee, share) in shares {
+        credit_claimable(deps, payee, &coin(share.u128(), &proceeds.denom), sale.cw20)?;
+    }
+    Ok(())
 }
 
-pub fn execute_mint(
-    mut deps: DepsMut,
+/// What `pay_seller` needs to know about an auction or listing that sold
+struct Sale<'a> {
+    collection: &'a Addr,
+    token_id: &'a str,
+    seller: &'a Addr,
+    payees: &'a [Payee],
+    cw20: bool,
+}
+
+impl<'a> From<&'a Auction> for Sale<'a> {
+    fn from(auction: &'a Auction) -> Self {
+        Sale {
+            collection: &auction.collection,
+            token_id: &auction.token_id,
+            seller: &auction.seller,
+            payees: &auction.payees,
+            cw20: auction.cw20,
+        }
+    }
+}
+
+/// Check sealed-bid auction still taking bids
+/// Hold funds sent as deposit
+/// Store commitment, one per bidder
+pub fn execute_commit_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    auction_id: u64,
+    commitment: Binary,
+) -> Result<Response, ContractError> {
+    let auction_val = auctions()
+        .may_load(deps.storage, auction_id.into())?
+        .ok_or_else(|| ContractError::InvalidAuction { reason: "auction does not exist".to_string() })?;
+    if !matches!(auction_val.kind, AuctionKind::Sealed(_)) {
+        return Err(ContractError::InvalidAuction { reason: "not a sealed-bid auction".to_string() });
+    }
+    assert_taking_bids(&auction_val, &env.block)?;
+    if commitment.len() != 32 {
+        return Err(ContractError::InvalidCommitment {});
+    }
+    // Bids below the reserve cannot win, neither can smaller deposits
+    let deposit = assert_sent_sufficient_coin(&info.funds, &auction_val.reserve_price)?;
+    if COMMITMENTS.has(deps.storage, (auction_id.into(), &info.sender)) {
+        return Err(ContractError::InvalidAuction { reason: "bid already committed".to_string() });
+    }
+    let bidders = COMMITMENTS
+        .prefix(auction_id.into())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .take(MAX_BIDDERS)
+        .count();
+    if bidders >= MAX_BIDDERS {
+        return Err(ContractError::InvalidAuction { reason: "auction has the maximum number of bidders".to_string() });
+    }
+    COMMITMENTS.save(deps.storage, (auction_id.into(), &info.sender), &Commitment {
+        bidder: info.sender.clone(),
+        hash: commitment,
+        deposit,
+        status: CommitmentStatus::Sealed {},
+    })?;
+    Ok(Response::new()
+        .add_attribute("method", "execute_commit_bid")
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("bidder", info.sender))
+}
+
+/// Check reveal phase
+/// Check revealed bid matches commitment
+/// Mark bids below the reserve or above the deposit invalid
+/// Track highest revealed bid on Auction
+pub fn execute_reveal_bid(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    auction_id: u64,
+    amount: Uint128,
+    salt: String,
+) -> Result<Response, ContractError> {
+    let mut auction_val = auctions()
+        .may_load(deps.storage, auction_id.into())?
+        .ok_or_else(|| ContractError::InvalidAuction { reason: "auction does not exist".to_string() })?;
+    let sealed = match &auction_val.kind {
+        AuctionKind::Sealed(sealed) => sealed,
+        _ => return Err(ContractError::InvalidAuction { reason: "not a sealed-bid auction".to_string() }),
+    };
+    match auction_val.status_at(&env.block) {
+        AuctionStatus::Ended {} => {}
+        AuctionStatus::Scheduled {} | AuctionStatus::Active {} => return Err(ContractError::AuctionNotEnded {}),
+        status => return Err(ContractError::InvalidAuction { reason: format!("auction is {}", status) }),
+    }
+    if sealed.reveal_close.is_expired(&env.block) {
+        return Err(ContractError::RevealClosed {});
+    }
+    let mut commitment = COMMITMENTS
+        .may_load(deps.storage, (auction_id.into(), &info.sender))?
+        .ok_or_else(|| ContractError::InvalidAuction { reason: "no bid committed".to_string() })?;
+    if commitment.status != (CommitmentStatus::Sealed {}) {
+        return Err(ContractError::InvalidAuction { reason: "bid already revealed".to_string() });
+    }
+    if commitment.hash != bid_commitment(info.sender.as_ref(), amount, &salt) {
+        return Err(ContractError::InvalidCommitment {});
+    }
+
+    let mut rsp = Response::new()
+        .add_attribute("method", "execute_reveal_bid")
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("bidder", &info.sender);
+    if amount < auction_val.reserve_price.amount || amount > commitment.deposit.amount {
+        commitment.status = CommitmentStatus::Invalid {};
+        rsp = rsp.add_attribute("valid", "0");
+    } else {
+        commitment.status = CommitmentStatus::Revealed { amount };
+        rsp = rsp.add_attribute("valid", "1");
+        record_bid(&mut deps, &env, auction_id, &info.sender, coin(amount.u128(), &auction_val.price.denom), None)?;
+        // Ties go to the earlier reveal
+        if auction_val.highest_bidder.is_none() || amount > auction_val.price.amount {
+            auction_val.price = coin(amount.u128(), &auction_val.price.denom);
+            auction_val.highest_bidder = Some(info.sender.clone());
+            auctions().save(deps.storage, auction_id.into(), &auction_val)?;
+        }
+    }
+    COMMITMENTS.save(deps.storage, (auction_id.into(), &info.sender), &commitment)?;
+    Ok(rsp)
+}
+
+/// Check multi-unit auction still taking bids
+/// Check funds cover quantity at unit price
+/// Credit back an earlier bid from the same bidder
+pub fn execute_bid_units(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    auction_id: u64,
+    quantity: Uint128,
+    unit_price: Uint128,
+) -> Result<Response, ContractError> {
+    let auction_val = auctions()
+        .may_load(deps.storage, auction_id.into())?
+        .ok_or_else(|| ContractError::InvalidAuction { reason: "auction does not exist".to_string() })?;
+    if auction_val.kind != (AuctionKind::MultiUnit {}) {
+        return Err(ContractError::InvalidAuction { reason: "not a multi-unit auction".to_string() });
+    }
+    assert_taking_bids(&auction_val, &env.block)?;
+    if quantity.is_zero() || quantity > auction_val.amount {
+        return Err(ContractError::InvalidAuction { reason: "quantity must be between 1 and the auctioned amount".to_string() });
+    }
+    if unit_price < auction_val.reserve_price.amount {
+        return Err(ContractError::InsufficientFundsSend {});
+    }
+    let total = coin(quantity.checked_mul(unit_price)?.u128(), &auction_val.reserve_price.denom);
+    let deposit = assert_sent_sufficient_coin(&info.funds, &total)?;
+
+    let rsp = Response::new()
+        .add_attribute("method", "execute_bid_units")
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("quantity", quantity)
+        .add_attribute("unit_price", unit_price);
+    match UNIT_BIDS.may_load(deps.storage, (auction_id.into(), &info.sender))? {
+        Some(previous) => credit_claimable(&mut deps, &info.sender, &previous.deposit, auction_val.cw20)?,
+        None => {
+            let bidders = UNIT_BIDS
+                .prefix(auction_id.into())
+                .keys(deps.storage, None, None, Order::Ascending)
+                .take(MAX_BIDDERS)
+                .count();
+            if bidders >= MAX_BIDDERS {
+                return Err(ContractError::InvalidAuction { reason: "auction has the maximum number of bidders".to_string() });
+            }
+        }
+    }
+    let unit_coin = coin(unit_price.u128(), &auction_val.reserve_price.denom);
+    record_bid(&mut deps, &env, auction_id, &info.sender, unit_coin, Some(quantity))?;
+    UNIT_BIDS.save(deps.storage, (auction_id.into(), &info.sender), &UnitBid {
+        bidder: info.sender.clone(),
+        quantity,
+        unit_price,
+        deposit,
+        height: env.block.height,
+    })?;
+    Ok(rsp)
+}
+
+/// Check seller or operator
+/// Check not settled and no bids placed
+/// Mark Auction cancelled and return NFT to seller
+pub fn execute_cancel_auction(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    auction_id: u64,
+) -> Result<Response, ContractError> {
+    let auction_val = auctions()
+        .may_load(deps.storage, auction_id.into())?
+        .ok_or_else(|| ContractError::InvalidAuction { reason: "auction does not exist".to_string() })?;
+    guard_can_approve(deps.as_ref(), &env, &auction_val.seller, &info.sender)?;
+    if auction_val.status != (AuctionStatus::Active {}) {
+        return Err(ContractError::InvalidAuction { reason: format!("auction is {}", auction_val.status) });
+    }
+    let has_commitments = COMMITMENTS
+        .prefix(auction_id.into())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    let has_unit_bids = UNIT_BIDS
+        .prefix(auction_id.into())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    if auction_val.highest_bidder.is_some() || has_commitments || has_unit_bids {
+        return Err(ContractError::InvalidAuction { reason: "auction already has bids".to_string() });
+    }
+    auctions().save(deps.storage, auction_id.into(), &Auction {
+        status: AuctionStatus::Cancelled {},
+        ..auction_val.clone()
+    })?;
+
+    let mut rsp = Response::default();
+    CancelAuctionEvent {
+        auction_id,
+        seller: auction_val.seller.as_ref(),
+        token_id: &auction_val.token_id,
+        amount: auction_val.amount,
+    }
+    .add_attributes(&mut rsp);
+    auction_transfer(&mut deps, &env, &auction_val, &auction_val.seller, &mut rsp)?;
+    Ok(rsp)
+}
+
+/// Store an accepted bid in the history under the next bid id
+fn record_bid(
+    deps: &mut DepsMut,
+    env: &Env,
+    auction_id: u64,
+    bidder: &Addr,
+    amount: Coin,
+    quantity: Option<Uint128>,
+) -> StdResult<()> {
+    let bid_id = BID_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    BID_COUNT.save(deps.storage, &bid_id)?;
+    bids().save(deps.storage, (auction_id.into(), bid_id.into()), &BidRecord {
+        auction_id,
+        bid_id,
+        bidder: bidder.clone(),
+        amount,
+        quantity,
+        height: env.block.height,
+        time: env.block.time,
+    })
+}
+
+/// Check auction is finished
+/// Remove up to `limit` of its oldest history entries
+pub fn execute_prune_bids(
+    deps: DepsMut,
     _env: Env,
+    _info: MessageInfo,
+    auction_id: u64,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let auction_val = auctions()
+        .may_load(deps.storage, auction_id.into())?
+        .ok_or_else(|| ContractError::InvalidAuction { reason: "auction does not exist".to_string() })?;
+    if auction_val.status == (AuctionStatus::Active {}) {
+        return Err(ContractError::InvalidAuction { reason: "bids are kept until the auction is finished".to_string() });
+    }
+    let limit = limit.unwrap_or(MAX_PRUNE_LIMIT).min(MAX_PRUNE_LIMIT) as usize;
+    let bid_ids = bids()
+        .prefix(auction_id.into())
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, bid)| bid.bid_id))
+        .collect::<StdResult<Vec<u64>>>()?;
+    for bid_id in bid_ids.iter() {
+        bids().remove(deps.storage, (auction_id.into(), (*bid_id).into()))?;
+    }
+    Ok(Response::new()
+        .add_attribute("method", "execute_prune_bids")
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("pruned", bid_ids.len().to_string()))
+}
+
+/// Check sender can act for the seller, accepted denom and sufficient tokens
+/// Move tokens into escrow
+/// Create listing under a new id
+pub fn execute_list_for_sale(
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    to: String,
+    seller: String,
     token_id: TokenId,
     amount: Uint128,
-    msg: Option<Binary>,
+    unit_price: Coin,
 ) -> Result<Response, ContractError> {
-    let to_addr = deps.api.addr_validate(&to)?;
+    if amount.is_zero() || unit_price.amount.is_zero() {
+        return Err(ContractError::InvalidListing { reason: "amount and unit price must not be zero".to_string() });
+    }
+    let seller_addr = deps.api.addr_validate(&seller)?;
+    guard_can_approve(deps.as_ref(), &env, &seller_addr, &info.sender)?;
+    let cw20 = accepted_denom(&CONFIG.load(deps.storage)?, &unit_price.denom)?;
+    let balance = BALANCES.may_load(deps.storage, (&seller_addr, &token_id))?.unwrap_or_default();
+    if balance < amount {
+        return Err(ContractError::InsufficientNftBalance {});
+    }
+    let listing_id = LISTING_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    LISTING_COUNT.save(deps.storage, &listing_id)?;
+    listings().save(deps.storage, listing_id.into(), &Listing {
+        seller: seller_addr.clone(),
+        token_id: token_id.clone(),
+        amount,
+        unit_price,
+        cw20,
+    })?;
+    let mut rsp = Response::new()
+        .add_attribute("method", "execute_list_for_sale")
+        .add_attribute("listing_id", listing_id.to_string())
+        .set_data(to_binary(&listing_id)?);
+    escrow_lock(&mut deps, &env, &seller_addr, &token_id, amount, &mut rsp)?;
+    Ok(rsp)
+}
 
-    if info.sender != MINTER.load(deps.storage)? {
-        return Err(ContractError::Unauthorized {});
+/// Check funds cover amount at the unit price
+/// Release the units from escrow to the buyer, pay the seller
+/// Credit overpayment back to buyer
+/// Remove the listing once sold out
+pub fn execute_buy(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut listing = listings()
+        .may_load(deps.storage, listing_id.into())?
+        .ok_or_else(|| ContractError::InvalidListing { reason: "listing does not exist".to_string() })?;
+    if amount.is_zero() || amount > listing.amount {
+        return Err(ContractError::InvalidListing { reason: "amount must be between 1 and the units left".to_string() });
+    }
+    let price = coin(amount.checked_mul(listing.unit_price.amount)?.u128(), &listing.unit_price.denom);
+    let sent_coin = assert_sent_sufficient_coin(&info.funds, &price)?;
+    let mut rsp = Response::new()
+        .add_attribute("method", "execute_buy")
+        .add_attribute("listing_id", listing_id.to_string())
+        .add_attribute("price", price.to_string());
+    if sent_coin.amount > price.amount {
+        let overpaid = coin((sent_coin.amount - price.amount).u128(), &price.denom);
+        credit_claimable(&mut deps, &info.sender, &overpaid, listing.cw20)?;
+    }
+    escrow_release(&mut deps, &env, &listing.seller, &info.sender, &listing.token_id, amount, &mut rsp)?;
+    let sale = Sale {
+        collection: &env.contract.address,
+        token_id: &listing.token_id,
+        seller: &listing.seller,
+        payees: &[],
+        cw20: listing.cw20,
+    };
+    pay_seller(&mut deps, &env, &sale, &price, &mut rsp)?;
+    listing.amount = listing.amount.checked_sub(amount)?;
+    if listing.amount.is_zero() {
+        listings().remove(deps.storage, listing_id.into())?;
+    } else {
+        listings().save(deps.storage, listing_id.into(), &listing)?;
     }
+    Ok(rsp)
+}
 
-    let mut rsp = Response::default();
+/// Check seller or operator
+/// Return the units left to the seller and remove the listing
+pub fn execute_delist(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: u64,
+) -> Result<Response, ContractError> {
+    let listing = listings()
+        .may_load(deps.storage, listing_id.into())?
+        .ok_or_else(|| ContractError::InvalidListing { reason: "listing does not exist".to_string() })?;
+    guard_can_approve(deps.as_ref(), &env, &listing.seller, &info.sender)?;
+    listings().remove(deps.storage, listing_id.into())?;
+    let mut rsp = Response::new()
+        .add_attribute("method", "execute_delist")
+        .add_attribute("listing_id", listing_id.to_string());
+    escrow_release(&mut deps, &env, &listing.seller, &listing.seller, &listing.token_id, listing.amount, &mut rsp)?;
+    Ok(rsp)
+}
 
-    let event = execute_transfer_inner(&mut deps, None, Some(&to_addr), &token_id, amount)?;
+/// Check owner holds the units and accepted denom
+/// Hold funds sent as the offered price
+/// Create offer under a new id
+pub fn execute_make_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    token_id: TokenId,
+    amount: Uint128,
+    expires: Expiration,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    if amount.is_zero() {
+        return Err(ContractError::InvalidOffer { reason: "amount must not be zero".to_string() });
+    }
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    let balance = BALANCES.may_load(deps.storage, (&owner_addr, &token_id))?.unwrap_or_default();
+    if balance < amount {
+        return Err(ContractError::InsufficientNftBalance {});
+    }
+    let price = assert_sent_one_coin(&info.funds)?;
+    let cw20 = accepted_denom(&CONFIG.load(deps.storage)?, &price.denom)?;
+    let offer_id = OFFER_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    OFFER_COUNT.save(deps.storage, &offer_id)?;
+    offers().save(deps.storage, offer_id.into(), &Offer {
+        offerer: info.sender,
+        owner: owner_addr,
+        token_id,
+        amount,
+        price,
+        expires,
+        cw20,
+    })?;
+    Ok(Response::new()
+        .add_attribute("method", "execute_make_offer")
+        .add_attribute("offer_id", offer_id.to_string())
+        .set_data(to_binary(&offer_id)?))
+}
+
+/// Check owner or operator, and offer not expired
+/// Transfer the units to the offerer, pay the owner
+/// Remove the offer
+pub fn execute_accept_offer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_id: u64,
+) -> Result<Response, ContractError> {
+    let offer = offers()
+        .may_load(deps.storage, offer_id.into())?
+        .ok_or_else(|| ContractError::InvalidOffer { reason: "offer does not exist".to_string() })?;
+    guard_can_approve(deps.as_ref(), &env, &offer.owner, &info.sender)?;
+    if offer.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    offers().remove(deps.storage, offer_id.into())?;
+    let mut rsp = Response::new()
+        .add_attribute("method", "execute_accept_offer")
+        .add_attribute("offer_id", offer_id.to_string());
+    let event = execute_transfer_inner(&mut deps, Some(&offer.owner), Some(&offer.offerer), &offer.token_id, offer.amount)?;
     event.add_attributes(&mut rsp);
+    let sale = Sale {
+        collection: &env.contract.address,
+        token_id: &offer.token_id,
+        seller: &offer.owner,
+        payees: &[],
+        cw20: offer.cw20,
+    };
+    pay_seller(&mut deps, &env, &sale, &offer.price, &mut rsp)?;
+    Ok(rsp)
+}
 
-    if let Some(msg) = msg {
-        rsp.messages = vec![SubMsg::new(
-            Cw1155ReceiveMsg {
-                operator: info.sender.to_string(),
-                from: None,
-                amount,
-                token_id: token_id.clone(),
-                msg,
-            }
-            .into_cosmos_msg(to)?,
-        )]
+/// Check offerer, or offer expired
+/// Credit the offered funds back to the offerer
+/// Remove the offer
+pub fn execute_withdraw_offer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_id: u64,
+) -> Result<Response, ContractError> {
+    let offer = offers()
+        .may_load(deps.storage, offer_id.into())?
+        .ok_or_else(|| ContractError::InvalidOffer { reason: "offer does not exist".to_string() })?;
+    if info.sender != offer.offerer && !offer.expires.is_expired(&env.block) {
+        return Err(ContractError::Unauthorized {});
     }
+    offers().remove(deps.storage, offer_id.into())?;
+    credit_claimable(&mut deps, &offer.offerer, &offer.price, offer.cw20)?;
+    Ok(Response::new()
+        .add_attribute("method", "execute_withdraw_offer")
+        .add_attribute("offer_id", offer_id.to_string()))
+}
 
-    // insert if not exist
-    if !TOKENS.has(deps.storage, &token_id) {
-        // we must save some valid data here
-        TOKENS.save(deps.storage, &token_id, &String::new())?;
+/// Check scope, accepted denom and funds cover quantity at the unit price
+/// Credit overpayment back to offerer
+/// Create collection offer under a new id
+pub fn execute_make_collection_offer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    scope: OfferScope,
+    unit_price: Coin,
+    quantity: Uint128,
+    expires: Expiration,
+) -> Result<Response, ContractError> {
+    if quantity.is_zero() || unit_price.amount.is_zero() {
+        return Err(ContractError::InvalidOffer { reason: "quantity and unit price must not be zero".to_string() });
     }
-    Ok(rsp)
+    if scope == (OfferScope::TokenIds { token_ids: vec![] }) {
+        return Err(ContractError::InvalidOffer { reason: "scope must list a token_id".to_string() });
+    }
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    let cw20 = accepted_denom(&CONFIG.load(deps.storage)?, &unit_price.denom)?;
+    let total = coin(quantity.checked_mul(unit_price.amount)?.u128(), &unit_price.denom);
+    let sent_coin = assert_sent_sufficient_coin(&info.funds, &total)?;
+    if sent_coin.amount > total.amount {
+        let overpaid = coin((sent_coin.amount - total.amount).u128(), &total.denom);
+        credit_claimable(&mut deps, &info.sender, &overpaid, cw20)?;
+    }
+    let offer_id = COLLECTION_OFFER_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    COLLECTION_OFFER_COUNT.save(deps.storage, &offer_id)?;
+    scope.index(deps.storage, offer_id)?;
+    collection_offers().save(deps.storage, offer_id.into(), &CollectionOffer {
+        offerer: info.sender,
+        scope,
+        unit_price,
+        quantity,
+        expires,
+        cw20,
+    })?;
+    Ok(Response::new()
+        .add_attribute("method", "execute_make_collection_offer")
+        .add_attribute("offer_id", offer_id.to_string())
+        .set_data(to_binary(&offer_id)?))
 }
 
-pub fn execute_send_from(
+/// Check owner or operator, offer not expired and token in scope
+/// Transfer the units to the offerer, pay the owner
+/// Remove the offer once filled
+pub fn execute_fill_collection_offer(
     mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    from: String,
-    to: String,
+    offer_id: u64,
+    owner: String,
     token_id: TokenId,
     amount: Uint128,
-    msg: Option<Binary>,
 ) -> Result<Response, ContractError> {
-    let from_addr = deps.api.addr_validate(&from)?;
-    let to_addr = deps.api.addr_validate(&to)?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let mut offer = collection_offers()
+        .may_load(deps.storage, offer_id.into())?
+        .ok_or_else(|| ContractError::InvalidOffer { reason: "offer does not exist".to_string() })?;
+    guard_can_approve(deps.as_ref(), &env, &owner_addr, &info.sender)?;
+    if offer.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    if !offer.scope.matches(&token_id) {
+        return Err(ContractError::InvalidOffer { reason: "token_id is not in the offer scope".to_string() });
+    }
+    if amount.is_zero() || amount > offer.quantity {
+        return Err(ContractError::InvalidOffer { reason: "amount must be between 1 and the units wanted".to_string() });
+    }
+    let price = coin(amount.checked_mul(offer.unit_price.amount)?.u128(), &offer.unit_price.denom);
+    let mut rsp = Response::new()
+        .add_attribute("method", "execute_fill_collection_offer")
+        .add_attribute("offer_id", offer_id.to_string())
+        .add_attribute("price", price.to_string());
+    let event = execute_transfer_inner(&mut deps, Some(&owner_addr), Some(&offer.offerer), &token_id, amount)?;
+    event.add_attributes(&mut rsp);
+    let sale = Sale {
+        collection: &env.contract.address,
+        token_id: &token_id,
+        seller: &owner_addr,
+        payees: &[],
+        cw20: offer.cw20,
+    };
+    pay_seller(&mut deps, &env, &sale, &price, &mut rsp)?;
+    offer.quantity = offer.quantity.checked_sub(amount)?;
+    if offer.quantity.is_zero() {
+        offer.scope.unindex(deps.storage, offer_id);
+        collection_offers().remove(deps.storage, offer_id.into())?;
+    } else {
+        collection_offers().save(deps.storage, offer_id.into(), &offer)?;
+    }
+    Ok(rsp)
+}
+
+/// Check offerer, or offer expired
+/// Credit the funds for the units still wanted back to the offerer
+/// Remove the offer
+pub fn execute_withdraw_collection_offer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_id: u64,
+) -> Result<Response, ContractError> {
+    let offer = collection_offers()
+        .may_load(deps.storage, offer_id.into())?
+        .ok_or_else(|| ContractError::InvalidOffer { reason: "offer does not exist".to_string() })?;
+    if info.sender != offer.offerer && !offer.expires.is_expired(&env.block) {
+        return Err(ContractError::Unauthorized {});
+    }
+    offer.scope.unindex(deps.storage, offer_id);
+    collection_offers().remove(deps.storage, offer_id.into())?;
+    let refund = coin(offer.quantity.checked_mul(offer.unit_price.amount)?.u128(), &offer.unit_price.denom);
+    credit_claimable(&mut deps, &offer.offerer, &refund, offer.cw20)?;
+    Ok(Response::new()
+        .add_attribute("method", "execute_withdraw_collection_offer")
+        .add_attribute("offer_id", offer_id.to_string()))
+}
+
+/// Pay out what the sender can claim in `denom`,
+/// native coins first, then the CW20 token at that address
+pub fn execute_withdraw(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    let rsp = Response::new().add_attribute("method", "execute_withdraw");
+    let amount = CLAIMABLE
+        .may_load(deps.storage, (&info.sender, &denom))?
+        .unwrap_or_default();
+    if !amount.is_zero() {
+        CLAIMABLE.remove(deps.storage, (&info.sender, &denom));
+        let payout = coin(amount.u128(), denom);
+        return Ok(rsp
+            .add_attribute("amount", payout.to_string())
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![payout],
+            }));
+    }
+    let token = Addr::unchecked(denom);
+    let amount = CLAIMABLE_CW20
+        .may_load(deps.storage, (&info.sender, &token))?
+        .unwrap_or_default();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+    CLAIMABLE_CW20.remove(deps.storage, (&info.sender, &token));
+    let payout = Cw20CoinVerified { address: token, amount };
+    Ok(rsp
+        .add_attribute("amount", format!("{}{}", payout.amount, payout.address))
+        .add_message(cw20_transfer(&info.sender, &payout)?))
+}
+
+/// Pay out what the sender can claim in every denom
+pub fn execute_withdraw_all(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let payout = query_claimable(deps.as_ref(), &info.sender)?;
+    let payout_cw20 = query_claimable_cw20(deps.as_ref(), &info.sender)?;
+    if payout.is_empty() && payout_cw20.is_empty() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+    let mut rsp = Response::new().add_attribute("method", "execute_withdraw_all");
+    for claimed in payout.iter() {
+        CLAIMABLE.remove(deps.storage, (&info.sender, &claimed.denom));
+    }
+    if !payout.is_empty() {
+        rsp = rsp.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: payout,
+        });
+    }
+    for claimed in payout_cw20.iter() {
+        CLAIMABLE_CW20.remove(deps.storage, (&info.sender, &claimed.address));
+        rsp = rsp.add_message(cw20_transfer(&info.sender, claimed)?);
+    }
+    Ok(rsp)
+}
+
+/// Transfer of `amount` from the contract's balance of the token
+fn cw20_transfer(recipient: &Addr, amount: &Cw20CoinVerified) -> StdResult<WasmMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: amount.address.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount: amount.amount,
+        })?,
+        funds: vec![],
+    })
+}
+
+/// Record `amount` as claimable by `owner`, refunds and proceeds are never pushed
+/// so a failing recipient cannot hold up an auction.
+/// `cw20` amounts are in the token at the address in the denom.
+fn credit_claimable(deps: &mut DepsMut, owner: &Addr, amount: &Coin, cw20: bool) -> StdResult<()> {
+    if amount.amount.is_zero() {
+        return Ok(());
+    }
+    if cw20 {
+        CLAIMABLE_CW20.update(
+            deps.storage,
+            (owner, &Addr::unchecked(&amount.denom)),
+            |claimable: Option<Uint128>| -> StdResult<_> {
+                Ok(claimable.unwrap_or_default().checked_add(amount.amount)?)
+            },
+        )?;
+        return Ok(());
+    }
+    CLAIMABLE.update(
+        deps.storage,
+        (owner, &amount.denom),
+        |claimable: Option<Uint128>| -> StdResult<_> {
+            Ok(claimable.unwrap_or_default().checked_add(amount.amount)?)
+        },
+    )?;
+    Ok(())
+}
+
+/// Move `amount` of `token_id` from `owner` into the contract's custody,
+/// the units stay recorded against `owner` in ESCROWS until released.
+fn escrow_lock(
+    deps: &mut DepsMut,
+    env: &Env,
+    owner: &Addr,
+    token_id: &str,
+    amount: Uint128,
+    rsp: &mut Response,
+) -> Result<(), ContractError> {
+    let event = execute_transfer_inner(deps, Some(owner), Some(&env.contract.address), token_id, amount)?;
+    event.add_attributes(rsp);
+    escrow_add(deps, owner, token_id, amount)
+}
+
+/// Record `amount` of `token_id` already held by the contract against `owner`
+fn escrow_add(
+    deps: &mut DepsMut,
+    owner: &Addr,
+    token_id: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    ESCROWS.update(
+        deps.storage,
+        (owner, token_id),
+        |escrowed: Option<Uint128>| -> StdResult<_> {
+            Ok(escrowed.unwrap_or_default().checked_add(amount)?)
+        },
+    )?;
+    Ok(())
+}
+
+/// Release `amount` of `token_id` escrowed on behalf of `owner` to `recipient`,
+/// pass `owner` as `recipient` to hand the units back.
+fn escrow_release(
+    deps: &mut DepsMut,
+    env: &Env,
+    owner: &Addr,
+    recipient: &Addr,
+    token_id: &str,
+    amount: Uint128,
+    rsp: &mut Response,
+) -> Result<(), ContractError> {
+    ESCROWS.update(
+        deps.storage,
+        (owner, token_id),
+        |escrowed: Option<Uint128>| -> StdResult<_> {
+            Ok(escrowed.unwrap_or_default().checked_sub(amount)?)
+        },
+    )?;
+    let event = execute_transfer_inner(deps, Some(&env.contract.address), Some(recipient), token_id, amount)?;
+    event.add_attributes(rsp);
+    Ok(())
+}
+
+/// Hand the auctioned tokens to `recipient`, out of escrow for tokens of this
+/// contract, or with a `SendFrom` to the external cw1155 contract holding them.
+fn auction_transfer(
+    deps: &mut DepsMut,
+    env: &Env,
+    auction: &Auction,
+    recipient: &Addr,
+    rsp: &mut Response,
+) -> Result<(), ContractError> {
+    auction_transfer_units(deps, env, auction, recipient, auction.amount, rsp)
+}
+
+/// Same as `auction_transfer` for part of the auctioned units,
+/// a failing external collection does not hold up settlement
+fn auction_transfer_units(
+    deps: &mut DepsMut,
+    env: &Env,
+    auction: &Auction,
+    recipient: &Addr,
+    amount: Uint128,
+    rsp: &mut Response,
+) -> Result<(), ContractError> {
+    if auction.collection == env.contract.address {
+        return escrow_release(deps, env, &auction.seller, recipient, &auction.token_id, amount, rsp);
+    }
+    let msg = external_send_from(env, auction, recipient, amount)?;
+    rsp.messages.push(SubMsg::reply_on_error(msg, RETURN_REPLY_ID));
+    Ok(())
+}
+
+/// Hand `amount` auctioned units to `buyer` who paid `price` for them.
+/// The seller of tokens of this contract is paid right away, the seller of an
+/// external collection in `reply` once the `SendFrom` went through,
+/// the buyer gets `price` back if it failed.
+#[allow(clippy::too_many_arguments)]
+fn auction_sell_units(
+    deps: &mut DepsMut,
+    env: &Env,
+    auction_id: u64,
+    auction: &Auction,
+    buyer: &Addr,
+    amount: Uint128,
+    price: &Coin,
+    rsp: &mut Response,
+) -> Result<(), ContractError> {
+    if auction.collection == env.contract.address {
+        escrow_release(deps, env, &auction.seller, buyer, &auction.token_id, amount, rsp)?;
+        return pay_seller(deps, env, &Sale::from(auction), price, rsp);
+    }
+    let reply_id = PENDING_SALE_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PENDING_SALE_COUNT.save(deps.storage, &reply_id)?;
+    PENDING_SALES.save(deps.storage, reply_id.into(), &PendingSale {
+        auction_id,
+        buyer: buyer.clone(),
+        price: price.clone(),
+    })?;
+    let msg = external_send_from(env, auction, buyer, amount)?;
+    rsp.messages.push(SubMsg::reply_always(msg, reply_id));
+    Ok(())
+}
+
+/// `SendFrom` of auctioned units held by the contract in an external collection
+fn external_send_from(env: &Env, auction: &Auction, recipient: &Addr, amount: Uint128) -> StdResult<WasmMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: auction.collection.to_string(),
+        msg: to_binary(&ExecuteMsg::SendFrom {
+            from: env.contract.address.to_string(),
+            to: recipient.to_string(),
+            token_id: auction.token_id.clone(),
+            value: amount,
+            msg: None,
+        })?,
+        funds: vec![],
+    })
+}
+
+/// When from is None: mint new coins
+/// When to is None: burn coins
+/// When both are None: no token balance is changed, pointless but valid
+/// Make sure permissions are checked before calling this.
+fn execute_transfer_inner<'a>(
+    deps: &'a mut DepsMut,
+    from: Option<&'a Addr>,
+    to: Option<&'a Addr>,
+    token_id: &'a str,
+    amount: Uint128,
+) -> Result<TransferEvent<'a>, ContractError> {
+    if let Some(from_addr) = from {
+        BALANCES.update(
+            deps.storage,
+            (from_addr, token_id),
+            |balance: Option<Uint128>| -> StdResult<_> {
+                Ok(balance.unwrap_or_default().checked_sub(amount)?)
+            },
+        )?;
+    }
+
+    if let Some(to_addr) = to {
+        BALANCES.update(
+            deps.storage,
+            (to_addr, token_id),
+            |balance: Option<Uint128>| -> StdResult<_> {
+                Ok(balance.unwrap_or_default().checked_add(amount)?)
+            },
+        )?;
+    }
+
+    Ok(TransferEvent {
+        from: from.map(|x| x.as_ref()),
+        to: to.map(|x| x.as_ref()),
+        token_id,
+        amount,
+    })
+}
+
+/// returns true iff the sender can execute approve or reject on the contract
+fn check_can_approve(deps: Deps, env: &Env, owner: &Addr, operator: &Addr) -> StdResult<bool> {
+    // owner can approve
+    if owner == operator {
+        return Ok(true);
+    }
+    // operator can approve
+    let op = APPROVES.may_load(deps.storage, (owner, operator))?;
+    Ok(match op {
+        Some(ex) => !ex.is_expired(&env.block),
+        None => false,
+    })
+}
+
+fn guard_can_approve(
+    deps: Deps,
+    env: &Env,
+    owner: &Addr,
+    operator: &Addr,
+) -> Result<(), ContractError> {
+    if !check_can_approve(deps, env, owner, operator)? {
+        Err(ContractError::Unauthorized {})
+    } else {
+        Ok(())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_mint(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    to: String,
+    token_id: TokenId,
+    amount: Uint128,
+    msg: Option<Binary>,
+    royalty: Option<RoyaltyMsg>,
+) -> Result<Response, ContractError> {
+    let to_addr = deps.api.addr_validate(&to)?;
+
+    if info.sender != MINTER.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut rsp = Response::default();
+
+    let event = execute_transfer_inner(&mut deps, None, Some(&to_addr), &token_id, amount)?;
+    event.add_attributes(&mut rsp);
+
+    if let Some(msg) = msg {
+        rsp.messages = vec![SubMsg::new(
+            Cw1155ReceiveMsg {
+                operator: info.sender.to_string(),
+                from: None,
+                amount,
+                token_id: token_id.clone(),
+                msg,
+            }
+            .into_cosmos_msg(to)?,
+        )]
+    }
+
+    // insert if not exist
+    if !TOKENS.has(deps.storage, &token_id) {
+        // we must save some valid data here
+        TOKENS.save(deps.storage, &token_id, &String::new())?;
+    }
+    if let Some(royalty) = royalty {
+        save_royalty(&mut deps, &token_id, royalty)?;
+    }
+    Ok(rsp)
+}
+
+/// Check minter
+/// Replace the royalty of token_id, or remove it
+pub fn execute_update_royalty(
+    mut deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    token_id: TokenId,
+    royalty: Option<RoyaltyMsg>,
+) -> Result<Response, ContractError> {
+    if info.sender != MINTER.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    match royalty {
+        Some(royalty) => save_royalty(&mut deps, &token_id, royalty)?,
+        None => ROYALTIES.remove(deps.storage, &token_id),
+    }
+    Ok(Response::new()
+        .add_attribute("method", "execute_update_royalty")
+        .add_attribute("token_id", token_id))
+}
+
+fn save_royalty(deps: &mut DepsMut, token_id: &str, royalty: RoyaltyMsg) -> Result<(), ContractError> {
+    if royalty.bps > 10_000 {
+        return Err(ContractError::InvalidRoyalty {});
+    }
+    let recipient = deps.api.addr_validate(&royalty.recipient)?;
+    ROYALTIES.save(deps.storage, token_id, &Royalty { recipient, bps: royalty.bps })?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_send_from(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: String,
+    to: String,
+    token_id: TokenId,
+    amount: Uint128,
+    msg: Option<Binary>,
+) -> Result<Response, ContractError> {
+    let from_addr = deps.api.addr_validate(&from)?;
+    let to_addr = deps.api.addr_validate(&to)?;
+
+    guard_can_approve(deps.as_ref(), &env, &from_addr, &info.sender)?;
+
+    let mut rsp = Response::default();
+
+    let event = execute_transfer_inner(
+        &mut deps,
+        Some(&from_addr),
+        Some(&to_addr),
+        &token_id,
+        amount,
+    )?;
+    event.add_attributes(&mut rsp);
+
+    if let Some(msg) = msg {
+        rsp.messages = vec![SubMsg::new(
+            Cw1155ReceiveMsg {
+                operator: info.sender.to_string(),
+                from: Some(from),
+                amount,
+                token_id: token_id.clone(),
+                msg,
+            }
+            .into_cosmos_msg(to)?,
+        )]
+    }
+    Ok(rsp)
+}
+
+pub fn execute_approve_all(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+
+    // reject expired data as invalid
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+
+    // set the operator for us
+    let operator_addr = deps.api.addr_validate(&operator)?;
+    APPROVES.save(deps.storage, (&info.sender, &operator_addr), &expires)?;
+
+    let mut rsp = Response::default();
+    ApproveAllEvent {
+        sender: info.sender.as_ref(),
+        operator: &operator,
+        approved: true,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+pub fn execute_revoke_all(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    operator: String
+) -> Result<Response, ContractError> {
+    let operator_addr = deps.api.addr_validate(&operator)?;
+    APPROVES.remove(deps.storage, (&info.sender, &operator_addr));
+
+    let mut rsp = Response::default();
+    ApproveAllEvent {
+        sender: info.sender.as_ref(),
+        operator: &operator,
+        approved: false,
+    }
+    .add_attributes(&mut rsp);
+    Ok(rsp)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetCount {} => to_binary(&query_count(deps)?),
+        QueryMsg::Config {} => to_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::RoyaltyInfo { token_id, sale_price } => {
+            let rsp = match ROYALTIES.may_load(deps.storage, &token_id)? {
+                Some(royalty) => RoyaltiesInfoResponse {
+                    address: royalty.recipient.to_string(),
+                    royalty_amount: sale_price.multiply_ratio(royalty.bps, 10_000u64),
+                },
+                None => RoyaltiesInfoResponse { address: String::new(), royalty_amount: Uint128::zero() },
+            };
+            to_binary(&rsp)
+        },
+        QueryMsg::Balance { owner, token_id } => {
+            let owner_addr = deps.api.addr_validate(&owner)?;
+            let balance = BALANCES
+                .may_load(deps.storage, (&owner_addr, &token_id))?
+                .unwrap_or_default();
+            let escrowed = ESCROWS
+                .may_load(deps.storage, (&owner_addr, &token_id))?
+                .unwrap_or_default();
+            to_binary(&BalanceResponse { balance, escrowed })
+        },
+        QueryMsg::IsApprovedForAll { owner, operator } => {
+            let owner_addr = deps.api.addr_validate(&owner)?;
+            let operator_addr = deps.api.addr_validate(&operator)?;
+            let approved = check_can_approve(deps, &env, &owner_addr, &operator_addr)?;
+            to_binary(&IsApprovedForAllResponse { approved })
+        },
+        QueryMsg::Auction { auction_id } => {
+            let auction = auctions()
+                .may_load(deps.storage, auction_id.into())?;
+            match auction {
+                None => Err(StdError::NotFound { kind: "invalid auction".to_string() }),
+                Some(auction_val) => to_binary(&auction_response(&env, auction_id, auction_val)?),
+            }
+        },
+        QueryMsg::ListAuctions { start_after, limit } => {
+            let start = start_after.map(Bound::exclusive_int);
+            let items = auctions().range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_auction_page(&env, items, limit)?)
+        },
+        QueryMsg::AuctionsBySeller { seller, start_after, limit } => {
+            let seller_addr = deps.api.addr_validate(&seller)?;
+            let start = start_after.map(Bound::exclusive_int);
+            let items = auctions()
+                .idx
+                .seller
+                .prefix(seller_addr.as_bytes().to_vec())
+                .range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_auction_page(&env, items, limit)?)
+        },
+        QueryMsg::AuctionsByToken { token_id, start_after, limit } => {
+            let start = start_after.map(Bound::exclusive_int);
+            let items = auctions()
+                .idx
+                .token_id
+                .prefix(token_id.as_bytes().to_vec())
+                .range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_auction_page(&env, items, limit)?)
+        },
+        QueryMsg::AuctionsByBidder { bidder, start_after, limit } => {
+            let bidder_addr = deps.api.addr_validate(&bidder)?;
+            let start = start_after.map(Bound::exclusive_int);
+            let items = auctions()
+                .idx
+                .highest_bidder
+                .prefix(bidder_addr.as_bytes().to_vec())
+                .range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_auction_page(&env, items, limit)?)
+        },
+        QueryMsg::BidHistory { auction_id, start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(Bound::exclusive_int);
+            let bids = bids()
+                .prefix(auction_id.into())
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, bid)| bid))
+                .collect::<StdResult<_>>()?;
+            to_binary(&BidsResponse { bids })
+        },
+        QueryMsg::BidsByBidder { bidder, start_after, limit } => {
+            let bidder_addr = deps.api.addr_validate(&bidder)?;
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(|(auction_id, bid_id)| {
+                Bound::exclusive((U64Key::from(auction_id), U64Key::from(bid_id)).joined_key())
+            });
+            let bids = bids()
+                .idx
+                .bidder
+                .prefix(bidder_addr.as_bytes().to_vec())
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, bid)| bid))
+                .collect::<StdResult<_>>()?;
+            to_binary(&BidsResponse { bids })
+        },
+        QueryMsg::Claimable { address } => {
+            let addr = deps.api.addr_validate(&address)?;
+            to_binary(&ClaimableResponse {
+                claimable: query_claimable(deps, &addr)?,
+                cw20: query_claimable_cw20(deps, &addr)?,
+            })
+        },
+        QueryMsg::AuctionsByStatus { status, start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(Bound::exclusive_int);
+            // live statuses share the stored active status, so the scan is capped
+            let items = auctions()
+                .idx
+                .status
+                .prefix(status.stored().to_string().into_bytes())
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(MAX_SCAN_LIMIT);
+            let mut res = AuctionsByStatusResponse { auctions: vec![], last_scanned: None };
+            for item in items {
+                let (pk, auction) = item?;
+                let auction_id = parse_id(&pk)?;
+                res.last_scanned = Some(auction_id);
+                if auction.status_at(&env.block) == status {
+                    res.auctions.push(auction_response(&env, auction_id, auction)?);
+                    if res.auctions.len() == limit {
+                        break;
+                    }
+                }
+            }
+            to_binary(&res)
+        },
+        QueryMsg::AuctionsEndingBefore { before, start_after, limit } => {
+            if before == (Expiration::Never {}) {
+                return Err(StdError::generic_err("before must be a height or a time"));
+            }
+            let (unit, before) = indexed_close(&before);
+            // index keys are the close followed by the auction id
+            let start = match start_after {
+                None => None,
+                Some((close, auction_id)) => {
+                    let (close_unit, close) = indexed_close(&close);
+                    if close_unit != unit {
+                        return Err(StdError::generic_err("start_after must close in the unit of before"));
+                    }
+                    Some(Bound::exclusive([close.to_be_bytes(), auction_id.to_be_bytes()].concat()))
+                }
+            };
+            let end = Some(Bound::exclusive(before.to_be_bytes().to_vec()));
+            let items = auctions()
+                .idx
+                .bidding_close
+                .prefix(unit)
+                .range(deps.storage, start, end, Order::Ascending);
+            to_binary(&query_auction_page(&env, items, limit)?)
+        },
+        QueryMsg::Listing { listing_id } => {
+            let listing = listings().load(deps.storage, listing_id.into())?;
+            to_binary(&ListingResponse { listing_id, listing })
+        },
+        QueryMsg::ListListings { start_after, limit } => {
+            let start = start_after.map(Bound::exclusive_int);
+            let items = listings().range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_listing_page(items, limit)?)
+        },
+        QueryMsg::ListingsBySeller { seller, start_after, limit } => {
+            let seller_addr = deps.api.addr_validate(&seller)?;
+            let start = start_after.map(Bound::exclusive_int);
+            let items = listings()
+                .idx
+                .seller
+                .prefix(seller_addr.as_bytes().to_vec())
+                .range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_listing_page(items, limit)?)
+        },
+        QueryMsg::ListingsByToken { token_id, start_after, limit } => {
+            let start = start_after.map(Bound::exclusive_int);
+            let items = listings()
+                .idx
+                .token_id
+                .prefix(token_id.as_bytes().to_vec())
+                .range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_listing_page(items, limit)?)
+        },
+        QueryMsg::Offer { offer_id } => {
+            let offer = offers().load(deps.storage, offer_id.into())?;
+            to_binary(&OfferResponse { offer_id, offer })
+        },
+        QueryMsg::OffersByToken { token_id, start_after, limit } => {
+            let start = start_after.map(Bound::exclusive_int);
+            let items = offers()
+                .idx
+                .token_id
+                .prefix(token_id.as_bytes().to_vec())
+                .range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_offer_page(items, limit)?)
+        },
+        QueryMsg::OffersByOfferer { offerer, start_after, limit } => {
+            let offerer_addr = deps.api.addr_validate(&offerer)?;
+            let start = start_after.map(Bound::exclusive_int);
+            let items = offers()
+                .idx
+                .offerer
+                .prefix(offerer_addr.as_bytes().to_vec())
+                .range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_offer_page(items, limit)?)
+        },
+        QueryMsg::CollectionOffer { offer_id } => {
+            let offer = collection_offers().load(deps.storage, offer_id.into())?;
+            to_binary(&CollectionOfferResponse { offer_id, offer })
+        },
+        QueryMsg::ListCollectionOffers { start_after, limit } => {
+            let start = start_after.map(Bound::exclusive_int);
+            let items = collection_offers().range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_collection_offer_page(items, limit)?)
+        },
+        QueryMsg::CollectionOffersByOfferer { offerer, start_after, limit } => {
+            let offerer_addr = deps.api.addr_validate(&offerer)?;
+            let start = start_after.map(Bound::exclusive_int);
+            let items = collection_offers()
+                .idx
+                .offerer
+                .prefix(offerer_addr.as_bytes().to_vec())
+                .range(deps.storage, start, None, Order::Ascending);
+            to_binary(&query_collection_offer_page(items, limit)?)
+        },
+        QueryMsg::CollectionOffersForToken { token_id, start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            // offers listing token_id, then those for each prefix of it, the empty one included
+            let mut pages = vec![COLLECTION_OFFER_TOKENS.prefix(&token_id)];
+            let prefix_ends = token_id.char_indices().map(|(end, _)| end).chain(std::iter::once(token_id.len()));
+            pages.extend(prefix_ends.map(|end| COLLECTION_OFFER_PREFIXES.prefix(&token_id[..end])));
+            let mut offer_ids = BTreeSet::new();
+            for page in pages {
+                let start = start_after.map(Bound::exclusive_int);
+                for pk in page.keys(deps.storage, start, None, Order::Ascending).take(limit) {
+                    offer_ids.insert(parse_id(&pk)?);
+                }
+            }
+            let offers = offer_ids
+                .into_iter()
+                .take(limit)
+                .map(|offer_id| {
+                    let offer = collection_offers().load(deps.storage, offer_id.into())?;
+                    Ok(CollectionOfferResponse { offer_id, offer })
+                })
+                .collect::<StdResult<_>>()?;
+            to_binary(&CollectionOffersResponse { offers })
+        },
+        QueryMsg::Commitment { auction_id, bidder } => {
+            let bidder_addr = deps.api.addr_validate(&bidder)?;
+            let commitment = COMMITMENTS.load(deps.storage, (auction_id.into(), &bidder_addr))?;
+            to_binary(&CommitmentResponse { commitment })
+        },
+        QueryMsg::Commitments { auction_id, start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(|bidder| Bound::exclusive(bidder.as_bytes()));
+            let commitments = COMMITMENTS
+                .prefix(auction_id.into())
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, commitment)| commitment))
+                .collect::<StdResult<_>>()?;
+            to_binary(&CommitmentsResponse { commitments })
+        },
+        QueryMsg::UnitBids { auction_id, start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.map(|bidder| Bound::exclusive(bidder.as_bytes()));
+            let bids = UNIT_BIDS
+                .prefix(auction_id.into())
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, bid)| bid))
+                .collect::<StdResult<_>>()?;
+            to_binary(&UnitBidsResponse { bids })
+        },
+    }
+}
+
+/// Take a page of at most `limit` auctions off `items`
+fn query_auction_page(
+    env: &Env,
+    items: Box<dyn Iterator<Item = StdResult<Pair<Auction>>> + '_>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let auctions = items
+        .take(limit)
+        .map(|item| {
+            let (pk, auction) = item?;
+            auction_response(env, parse_id(&pk)?, auction)
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(AuctionsResponse { auctions })
+}
+
+/// Take a page of at most `limit` listings off `items`
+fn query_listing_page(
+    items: Box<dyn Iterator<Item = StdResult<Pair<Listing>>> + '_>,
+    limit: Option<u32>,
+) -> StdResult<ListingsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let listings = items
+        .take(limit)
+        .map(|item| {
+            let (pk, listing) = item?;
+            Ok(ListingResponse { listing_id: parse_id(&pk)?, listing })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ListingsResponse { listings })
+}
+
+/// Take a page of at most `limit` offers off `items`
+fn query_offer_page(
+    items: Box<dyn Iterator<Item = StdResult<Pair<Offer>>> + '_>,
+    limit: Option<u32>,
+) -> StdResult<OffersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let offers = items
+        .take(limit)
+        .map(|item| {
+            let (pk, offer) = item?;
+            Ok(OfferResponse { offer_id: parse_id(&pk)?, offer })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(OffersResponse { offers })
+}
+
+/// Take a page of at most `limit` collection offers off `items`
+fn query_collection_offer_page(
+    items: Box<dyn Iterator<Item = StdResult<Pair<CollectionOffer>>> + '_>,
+    limit: Option<u32>,
+) -> StdResult<CollectionOffersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let offers = items
+        .take(limit)
+        .map(|item| {
+            let (pk, offer) = item?;
+            Ok(CollectionOfferResponse { offer_id: parse_id(&pk)?, offer })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(CollectionOffersResponse { offers })
+}
+
+/// Read back an auction, listing or offer id from its primary key
+fn parse_id(pk: &[u8]) -> StdResult<u64> {
+    pk.try_into()
+        .map(u64::from_be_bytes)
+        .map_err(|_| StdError::parse_err("id", "expected 8 bytes"))
+}
+
+fn auction_response(env: &Env, auction_id: u64, auction: Auction) -> StdResult<AuctionResponse> {
+    Ok(AuctionResponse {
+        auction_id,
+        next_min_bid: auction.next_min_bid(&env.block)?,
+        status: auction.status_at(&env.block),
+        auction,
+    })
+}
+
+/// Everything claimable by `owner`, sorted by denom
+fn query_claimable(deps: Deps, owner: &Addr) -> StdResult<Vec<Coin>> {
+    CLAIMABLE
+        .prefix(owner)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, amount) = item?;
+            let denom = String::from_utf8(denom).map_err(StdError::invalid_utf8)?;
+            Ok(coin(amount.u128(), denom))
+        })
+        .collect()
+}
+
+/// Every CW20 token claimable by `owner`, sorted by token address
+fn query_claimable_cw20(deps: Deps, owner: &Addr) -> StdResult<Vec<Cw20CoinVerified>> {
+    CLAIMABLE_CW20
+        .prefix(owner)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (token, amount) = item?;
+            let token = String::from_utf8(token).map_err(StdError::invalid_utf8)?;
+            Ok(Cw20CoinVerified { address: Addr::unchecked(token), amount })
+        })
+        .collect()
+}
+
+fn query_count(deps: Deps) -> StdResult<CountResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(CountResponse { count: state.count })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{coins, ReplyOn, SubMsgExecutionResponse};
+
+    fn claimable(deps: Deps, address: &str) -> Vec<Coin> {
+        let res = query(deps, mock_env(), QueryMsg::Claimable { address: address.to_string() }).unwrap();
+        let value: ClaimableResponse = from_binary(&res).unwrap();
+        value.claimable
+    }
+
+    /// Instantiate message with "minter" as minter and the default config
+    fn instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg { count: 17, minter: "minter".to_string(), default_min_increment: None, accepted_denoms: None, cw20_whitelist: None, protocol_fee_bps: None, treasury: None }
+    }
+
+    /// Instantiate with `msg`, then have the minter mint every `(to, token_id, value)`
+    fn setup(mut deps: DepsMut, msg: InstantiateMsg, mints: &[(&str, &str, u128)]) {
+        instantiate(deps.branch(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        for (to, token_id, value) in mints {
+            let msg = ExecuteMsg::Mint {
+                to: to.to_string(),
+                token_id: token_id.to_string(),
+                value: Uint128::new(*value),
+                msg: None,
+                royalty: None,
+            };
+            execute(deps.branch(), mock_env(), mock_info("minter", &[]), msg).unwrap();
+        }
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg { minter: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(), ..instantiate_msg() };
+        let info = mock_info("creator", &coins(1000, "ust"));
+
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let value: CountResponse = from_binary(&res).unwrap();
+        assert_eq!(17, value.count);
+    }
+    /// Instantiate
+    /// Mint Token
+    /// Create Auction
+    /// Bid Auction
+    /// Close Auction
+    #[test]
+    fn auction() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg { minter: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(), ..instantiate_msg() };
+        let info = mock_info("creator", &coins(1000, "uusd"));
+
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // mint token
+        // ===================
+        let info = mock_info("terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8", &[]);
+        let msg = ExecuteMsg::Mint {
+            to: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
+            token_id: "ID1".to_string(),
+            value: Uint128::new(10001u128),
+            msg: None,
+            royalty: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // query nft token
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
+            token_id: "ID1".to_string()
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(10001u128), value.balance);
+
+        // create auction
+        // ===================
+        let info = mock_info("terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8", &[]);
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        // creating an auction takes no funds
+        let paid = mock_info("terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8", &coins(1000, "uusd"));
+        let err = execute(deps.as_mut(), mock_env(), paid, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::UnexpectedFunds { .. }));
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // auctioned units are held in escrow
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
+            token_id: "ID1".to_string()
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(10000u128), value.balance);
+        assert_eq!(Uint128::new(1u128), value.escrowed);
+
+        // query auction
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Auction {
+            auction_id: 1,
+        }).unwrap();
+        let value: AuctionResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(1u128), value.auction.amount);
+        assert_eq!(coin(1000, "uusd"), value.auction.price);
+        assert_eq!(Expiration::AtHeight(23123), value.auction.bidding_close);
+
+        // place bid
+        // ===================
+        let info = mock_info("terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd9", &coins(10000, "uusd"));
+        let msg = ExecuteMsg::Bid {
+            auction_id: 1,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // nobody to refund on the first bid
+        assert_eq!(0, res.messages.len());
+
+        // query auction
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Auction {
+            auction_id: 1,
+        }).unwrap();
+        let value: AuctionResponse = from_binary(&res).unwrap();
+        assert_eq!(coin(10000, "uusd"), value.auction.price);
+
+        // close auction
+        // ===================
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::CloseAuction {
+            auction_id: 1,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionNotEnded {}));
+
+        let mut env = mock_env();
+        env.block.height = 23123;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &coins(1, "uusd")), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::UnexpectedFunds { .. }));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+        // cannot settle twice
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+
+        // query nft token
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd9".to_string(),
+            token_id: "ID1".to_string()
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(1u128), value.balance);
+
+        // escrow is released
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
+            token_id: "ID1".to_string()
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(10000u128), value.balance);
+        assert_eq!(Uint128::zero(), value.escrowed);
+    }
+
+    #[test]
+    fn concurrent_auctions_of_one_token() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 5)]);
+
+        // the same seller lists the same token twice
+        let create = |amount: u128| ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(amount),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(2)).unwrap();
+        assert!(res.attributes.contains(&attr("auction_id", "1")));
+        assert_eq!(1u64, from_binary::<u64>(&res.data.unwrap()).unwrap());
+        let res = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(3)).unwrap();
+        assert!(res.attributes.contains(&attr("auction_id", "2")));
+        assert_eq!(2u64, from_binary::<u64>(&res.data.unwrap()).unwrap());
+
+        let bid = ExecuteMsg::Bid { auction_id: 2 };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uusd")), bid).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Auction { auction_id: 1 }).unwrap();
+        let value: AuctionResponse = from_binary(&res).unwrap();
+        assert_eq!(1, value.auction_id);
+        assert_eq!(Uint128::new(2u128), value.auction.amount);
+        assert_eq!(None, value.auction.highest_bidder);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Auction { auction_id: 2 }).unwrap();
+        let value: AuctionResponse = from_binary(&res).unwrap();
+        assert_eq!(Addr::unchecked("seller"), value.auction.seller);
+        assert_eq!("ID1", value.auction.token_id);
+        assert_eq!(Some(Addr::unchecked("bidder")), value.auction.highest_bidder);
+
+        let err = query(deps.as_ref(), mock_env(), QueryMsg::Auction { auction_id: 3 }).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+    }
+
+    #[test]
+    fn list_auctions() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller1", "ID1", 1), ("seller1", "ID2", 1), ("seller2", "ID1", 1)]);
+
+        let create = |seller: &str, token_id: &str, bidding_close: Expiration| ExecuteMsg::CreateAuction {
+            token_id: token_id.to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: seller.to_string(),
+            bidding_start: None,
+            bidding_close,
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        let close_time = mock_env().block.time.plus_seconds(3600);
+        execute(deps.as_mut(), mock_env(), mock_info("seller1", &[]), create("seller1", "ID1", Expiration::AtHeight(23000))).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("seller1", &[]), create("seller1", "ID2", Expiration::AtHeight(22000))).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("seller2", &[]), create("seller2", "ID1", Expiration::AtTime(close_time))).unwrap();
+        let bid = ExecuteMsg::Bid { auction_id: 3 };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uusd")), bid).unwrap();
+
+        let ids = |deps: Deps, msg: QueryMsg| -> Vec<u64> {
+            let res = query(deps, mock_env(), msg).unwrap();
+            let value: AuctionsResponse = from_binary(&res).unwrap();
+            value.auctions.iter().map(|auction| auction.auction_id).collect()
+        };
+        assert_eq!(vec![1, 2], ids(deps.as_ref(), QueryMsg::ListAuctions { start_after: None, limit: Some(2) }));
+        assert_eq!(vec![3], ids(deps.as_ref(), QueryMsg::ListAuctions { start_after: Some(2), limit: Some(2) }));
+        assert_eq!(vec![1, 2], ids(deps.as_ref(), QueryMsg::AuctionsBySeller { seller: "seller1".to_string(), start_after: None, limit: None }));
+        assert_eq!(vec![2], ids(deps.as_ref(), QueryMsg::AuctionsBySeller { seller: "seller1".to_string(), start_after: Some(1), limit: None }));
+        assert_eq!(vec![1, 3], ids(deps.as_ref(), QueryMsg::AuctionsByToken { token_id: "ID1".to_string(), start_after: None, limit: None }));
+        assert_eq!(vec![3], ids(deps.as_ref(), QueryMsg::AuctionsByBidder { bidder: "bidder".to_string(), start_after: None, limit: None }));
+
+        // soonest first, in the unit asked for
+        let ending_before = |before: Expiration, start_after: Option<(Expiration, u64)>| QueryMsg::AuctionsEndingBefore { before, start_after, limit: None };
+        assert_eq!(vec![2, 1], ids(deps.as_ref(), ending_before(Expiration::AtHeight(23001), None)));
+        assert_eq!(vec![1], ids(deps.as_ref(), ending_before(Expiration::AtHeight(23001), Some((Expiration::AtHeight(22000), 2)))));
+        let cursor = Some((Expiration::AtTime(close_time), 2));
+        assert!(query(deps.as_ref(), mock_env(), ending_before(Expiration::AtHeight(23001), cursor)).is_err());
+        assert_eq!(vec![2], ids(deps.as_ref(), ending_before(Expiration::AtHeight(23000), None)));
+        assert_eq!(vec![3], ids(deps.as_ref(), ending_before(Expiration::AtTime(close_time.plus_seconds(1)), None)));
+        assert!(ids(deps.as_ref(), ending_before(Expiration::AtTime(close_time), None)).is_empty());
+
+        // settled auctions stay listed under their final status
+        let mut env = mock_env();
+        env.block.height = 23000;
+        execute(deps.as_mut(), env, mock_info("anyone", &[]), ExecuteMsg::CloseAuction { auction_id: 1 }).unwrap();
+        assert_eq!(vec![1, 2], ids(deps.as_ref(), QueryMsg::AuctionsBySeller { seller: "seller1".to_string(), start_after: None, limit: None }));
+        let by_status = |deps: Deps, status: AuctionStatus, start_after: Option<u64>| -> (Vec<u64>, Option<u64>) {
+            let msg = QueryMsg::AuctionsByStatus { status, start_after, limit: Some(1) };
+            let value: AuctionsByStatusResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            (value.auctions.iter().map(|auction| auction.auction_id).collect(), value.last_scanned)
+        };
+        assert_eq!((vec![1], Some(1)), by_status(deps.as_ref(), AuctionStatus::NoSale {}, None));
+        assert_eq!((vec![], None), by_status(deps.as_ref(), AuctionStatus::NoSale {}, Some(1)));
+        assert_eq!((vec![2], Some(2)), by_status(deps.as_ref(), AuctionStatus::Active {}, None));
+        assert_eq!((vec![3], Some(3)), by_status(deps.as_ref(), AuctionStatus::Active {}, Some(2)));
+        assert_eq!((vec![], Some(3)), by_status(deps.as_ref(), AuctionStatus::Ended {}, None));
+    }
+
+    #[test]
+    fn escrowed_tokens_cannot_be_sent() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 5)]);
+
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(5u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        // the seller no longer holds the units
+        let msg = ExecuteMsg::SendFrom {
+            from: "seller".to_string(),
+            to: "other".to_string(),
+            token_id: "ID1".to_string(),
+            value: Uint128::new(1u128),
+            msg: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        // nobody else can list the seller's tokens
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("other", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn reserve_price_and_refunds() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 1)]);
+
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        let bid = ExecuteMsg::Bid {
+            auction_id: 1,
+        };
+        // below the reserve
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder1", &coins(999, "uusd")), bid.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFundsSend {}));
+
+        // meeting the reserve is enough for the first bid
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bidder1", &coins(1000, "uusd")), bid.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // later bids must beat the highest bid
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder2", &coins(1000, "uusd")), bid.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFundsSend {}));
+
+        // and credit the previous bidder a refund to withdraw
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bidder2", &coins(1500, "uusd")), bid).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(coins(1000, "uusd"), claimable(deps.as_ref(), "bidder1"));
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder1", &[]), ExecuteMsg::Withdraw { denom: "uluna".to_string() }).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bidder1", &[]), ExecuteMsg::Withdraw { denom: "uusd".to_string() }).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "bidder1".to_string(),
+                amount: coins(1000, "uusd"),
+            })]
+        );
+        assert!(claimable(deps.as_ref(), "bidder1").is_empty());
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder1", &[]), ExecuteMsg::Withdraw { denom: "uusd".to_string() }).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+    }
+
+    #[test]
+    fn bid_history() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 2)]);
+
+        let create = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create).unwrap();
+
+        let mut env = mock_env();
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &coins(1000, "uusd")), ExecuteMsg::Bid { auction_id: 1 }).unwrap();
+        env.block.height += 1;
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &coins(1200, "uusd")), ExecuteMsg::Bid { auction_id: 1 }).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &coins(1000, "uusd")), ExecuteMsg::Bid { auction_id: 2 }).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::BidHistory { auction_id: 1, start_after: None, limit: None }).unwrap();
+        let value: BidsResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            value.bids,
+            vec![
+                BidRecord {
+                    auction_id: 1,
+                    bid_id: 1,
+                    bidder: Addr::unchecked("alice"),
+                    amount: coin(1000, "uusd"),
+                    quantity: None,
+                    height: mock_env().block.height,
+                    time: mock_env().block.time,
+                },
+                BidRecord {
+                    auction_id: 1,
+                    bid_id: 2,
+                    bidder: Addr::unchecked("bob"),
+                    amount: coin(1200, "uusd"),
+                    quantity: None,
+                    height: env.block.height,
+                    time: env.block.time,
+                },
+            ]
+        );
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::BidHistory { auction_id: 1, start_after: Some(1), limit: None }).unwrap();
+        let value: BidsResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![2], value.bids.iter().map(|bid| bid.bid_id).collect::<Vec<_>>());
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::BidsByBidder { bidder: "alice".to_string(), start_after: None, limit: None }).unwrap();
+        let value: BidsResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![(1, 1), (2, 3)], value.bids.iter().map(|bid| (bid.auction_id, bid.bid_id)).collect::<Vec<_>>());
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::BidsByBidder { bidder: "alice".to_string(), start_after: Some((1, 1)), limit: None }).unwrap();
+        let value: BidsResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![(2, 3)], value.bids.iter().map(|bid| (bid.auction_id, bid.bid_id)).collect::<Vec<_>>());
+
+        // history is kept until the auction is finished
+        let prune = ExecuteMsg::PruneBids { auction_id: 1, limit: Some(1) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), prune.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        env.block.height = 23123;
+        execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), ExecuteMsg::CloseAuction { auction_id: 1 }).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), prune.clone()).unwrap();
+        assert!(res.attributes.contains(&attr("pruned", "1")));
+        execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), prune).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::BidHistory { auction_id: 1, start_after: None, limit: None }).unwrap();
+        let value: BidsResponse = from_binary(&res).unwrap();
+        assert!(value.bids.is_empty());
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::BidsByBidder { bidder: "alice".to_string(), start_after: None, limit: None }).unwrap();
+        let value: BidsResponse = from_binary(&res).unwrap();
+        assert_eq!(1, value.bids.len());
+    }
+
+    #[test]
+    fn bid_increment() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg { default_min_increment: Some(BidIncrement::BasisPoints { bps: 500 }), ..instantiate_msg() };
+        setup(deps.as_mut(), msg, &[("seller", "ID1", 2)]);
+
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        let bid = ExecuteMsg::Bid {
+            auction_id: 1,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder1", &coins(1000, "uusd")), bid.clone()).unwrap();
+
+        // 5% of the highest bid on top
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Auction {
+            auction_id: 1,
+        }).unwrap();
+        let value: AuctionResponse = from_binary(&res).unwrap();
+        assert_eq!(coin(1050, "uusd"), value.next_min_bid);
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder2", &coins(1049, "uusd")), bid.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFundsSend {}));
+        execute(deps.as_mut(), mock_env(), mock_info("bidder2", &coins(1050, "uusd")), bid).unwrap();
+
+        // auctions can override the default
+        let msg = ExecuteMsg::Mint {
+            to: "seller2".to_string(),
+            token_id: "ID1".to_string(),
+            value: Uint128::new(1u128),
+            msg: None,
+            royalty: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap();
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller2".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: Some(BidIncrement::Absolute { amount: Uint128::new(100u128) }),
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller2", &[]), msg).unwrap();
+        let bid = ExecuteMsg::Bid {
+            auction_id: 2,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder1", &coins(1000, "uusd")), bid).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Auction {
+            auction_id: 2,
+        }).unwrap();
+        let value: AuctionResponse = from_binary(&res).unwrap();
+        assert_eq!(coin(1100, "uusd"), value.next_min_bid);
+
+        // percentages above 100% are rejected
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: Some(BidIncrement::BasisPoints { bps: 10_001 }),
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidBidIncrement {}));
+    }
+
+    #[test]
+    fn anti_snipe_extends_close() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 1)]);
+
+        let close = mock_env().block.time.plus_seconds(3600);
+        let create = |max_close: Expiration| ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtTime(close),
+            min_increment: None,
+            anti_snipe: Some(AntiSnipe {
+                window: Duration::Time(300),
+                extend_by: Duration::Time(300),
+                max_close,
+            }),
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        // the cap must be a real one
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(Expiration::Never {})).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(Expiration::AtTime(close.plus_seconds(400)))).unwrap();
+
+        let bid = ExecuteMsg::Bid {
+            auction_id: 1,
+        };
+        // early bids leave the close alone
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bidder1", &coins(1000, "uusd")), bid.clone()).unwrap();
+        assert_eq!(2, res.attributes.len());
+
+        // a bid inside the window extends it
+        let mut env = mock_env();
+        env.block.time = close.minus_seconds(100);
+        let res = execute(deps.as_mut(), env.clone(), mock_info("bidder2", &coins(1100, "uusd")), bid.clone()).unwrap();
+        assert_eq!(
+            res.attributes[2],
+            attr("bidding_close_extended", Expiration::AtTime(close.plus_seconds(300)).to_string())
+        );
+
+        // up to the cap
+        env.block.time = close.plus_seconds(200);
+        execute(deps.as_mut(), env.clone(), mock_info("bidder1", &coins(1200, "uusd")), bid.clone()).unwrap();
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Auction {
+            auction_id: 1,
+        }).unwrap();
+        let value: AuctionResponse = from_binary(&res).unwrap();
+        assert_eq!(Expiration::AtTime(close.plus_seconds(400)), value.auction.bidding_close);
+
+        env.block.time = close.plus_seconds(400);
+        let err = execute(deps.as_mut(), env, mock_info("bidder2", &coins(1300, "uusd")), bid).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionEnded {}));
+    }
+
+    #[test]
+    fn buy_now_settles_instantly() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 1)]);
+
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: Some(coin(5000, "uusd")),
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        let bid = ExecuteMsg::Bid {
+            auction_id: 1,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder1", &coins(1000, "uusd")), bid.clone()).unwrap();
+
+        // meeting the buy now price refunds, pays out and transfers at once,
+        // overpaying bids only pay the buy now price
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bidder2", &coins(6000, "uusd")), bid.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(coins(1000, "uusd"), claimable(deps.as_ref(), "bidder1"));
+        assert_eq!(coins(1000, "uusd"), claimable(deps.as_ref(), "bidder2"));
+        assert_eq!(coins(5000, "uusd"), claimable(deps.as_ref(), "seller"));
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "bidder2".to_string(),
+            token_id: "ID1".to_string(),
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(1u128), value.balance);
+
+        // the auction is over
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder1", &coins(6000, "uusd")), bid).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+
+        // buy now below the reserve is rejected
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "bidder2".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: Some(coin(999, "uusd")),
+            kind: None,
+            payees: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder2", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+
+        // the buy now price takes the auction even when below the next minimum bid
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(90, "uusd"),
+            seller: "bidder2".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: Some(BidIncrement::Absolute { amount: Uint128::new(10u128) }),
+            anti_snipe: None,
+            buy_now_price: Some(coin(100, "uusd")),
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder2", &[]), msg).unwrap();
+        let bid = ExecuteMsg::Bid { auction_id: 2 };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder1", &coins(95, "uusd")), bid.clone()).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bidder3", &coins(100, "uusd")), bid).unwrap();
+        assert!(res.attributes.contains(&attr("buy_now", "100uusd")));
+        assert_eq!(coins(1095, "uusd"), claimable(deps.as_ref(), "bidder1"));
+        assert_eq!(coins(1100, "uusd"), claimable(deps.as_ref(), "bidder2"));
+    }
+
+    #[test]
+    fn dutch_auction() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 1)]);
+
+        let height = mock_env().block.height;
+        let create = |decay_end: Expiration| ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(height + 200),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: Some(AuctionKind::Dutch(DutchAuction {
+                start_price: coin(2000, "uusd"),
+                decay_start: Expiration::AtHeight(height),
+                decay_end,
+                decay: PriceDecay::Stepped { steps: 4 },
+            })),
+            payees: None,
+        };
+        // decay has to end
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(Expiration::Never {})).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        // nor run past bidding_close
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(Expiration::AtHeight(height + 201))).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(Expiration::AtHeight(height + 100))).unwrap();
+
+        // the live price is computed at the queried block
+        let query_price = |deps: Deps, height: u64| {
+            let mut env = mock_env();
+            env.block.height = height;
+            let res = query(deps, env, QueryMsg::Auction {
+                auction_id: 1,
+            }).unwrap();
+            let value: AuctionResponse = from_binary(&res).unwrap();
+            value.next_min_bid
+        };
+        assert_eq!(coin(2000, "uusd"), query_price(deps.as_ref(), height));
+        assert_eq!(coin(2000, "uusd"), query_price(deps.as_ref(), height + 24));
+        assert_eq!(coin(1750, "uusd"), query_price(deps.as_ref(), height + 25));
+        assert_eq!(coin(1500, "uusd"), query_price(deps.as_ref(), height + 60));
+        assert_eq!(coin(1000, "uusd"), query_price(deps.as_ref(), height + 150));
+
+        let bid = ExecuteMsg::Bid {
+            auction_id: 1,
+        };
+        let mut env = mock_env();
+        env.block.height = height + 60;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("buyer", &coins(1499, "uusd")), bid.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFundsSend {}));
+
+        // the first buyer at the live price wins, overpayment is returned
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &coins(1600, "uusd")), bid.clone()).unwrap();
+        assert_eq!(coins(100, "uusd"), claimable(deps.as_ref(), "buyer"));
+        assert_eq!(coins(1500, "uusd"), claimable(deps.as_ref(), "seller"));
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "buyer".to_string(),
+            token_id: "ID1".to_string(),
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(1u128), value.balance);
+
+        let err = execute(deps.as_mut(), env, mock_info("other", &coins(2000, "uusd")), bid).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+    }
+
+    #[test]
+    fn sealed_bid_vickrey() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 1)]);
+
+        let create = |reveal_close: Expiration| ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(20000),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: Some(AuctionKind::Sealed(SealedAuction {
+                reveal_close,
+                pricing: SealedPricing::SecondPrice {},
+                penalty: CommitmentPenalty::Forfeit {},
+            })),
+            payees: None,
+        };
+        // the reveal phase has to end for the auction to settle
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(Expiration::Never {})).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(Expiration::AtHeight(21000))).unwrap();
+
+        // open bids are refused, whatever they pay
+        let msg = ExecuteMsg::Bid {
+            auction_id: 1,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(3000, "uusd")), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+
+        // deposits may hide the real bid
+        let commit = |bidder: &str, amount: u128| ExecuteMsg::CommitBid {
+            auction_id: 1,
+            commitment: bid_commitment(bidder, Uint128::new(amount), "salt"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(4000, "uusd")), commit("alice", 3000)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(2000, "uusd")), commit("bob", 2000)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &coins(1500, "uusd")), commit("carol", 1500)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("dave", &coins(1000, "uusd")), commit("dave", 5000)).unwrap();
+
+        let reveal = |amount: u128, salt: &str| ExecuteMsg::RevealBid {
+            auction_id: 1,
+            amount: Uint128::new(amount),
+            salt: salt.to_string(),
+        };
+        // not before bidding closes
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), reveal(3000, "salt")).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionNotEnded {}));
+
+        let mut env = mock_env();
+        env.block.height = 20000;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("late", &coins(9000, "uusd")), commit("late", 9000)).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionEnded {}));
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), reveal(3000, "pepper")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCommitment {}));
+
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), reveal(3000, "salt")).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), reveal(2000, "salt")).unwrap();
+        // a bid larger than its deposit is invalid
+        execute(deps.as_mut(), env.clone(), mock_info("dave", &[]), reveal(5000, "salt")).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Commitment {
+            auction_id: 1,
+            bidder: "dave".to_string(),
+        }).unwrap();
+        let value: CommitmentResponse = from_binary(&res).unwrap();
+        assert_eq!(CommitmentStatus::Invalid {}, value.commitment.status);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Commitments {
+            auction_id: 1,
+            start_after: Some("bob".to_string()),
+            limit: Some(1),
+        }).unwrap();
+        let value: CommitmentsResponse = from_binary(&res).unwrap();
+        assert_eq!(1, value.commitments.len());
+        assert_eq!(Addr::unchecked("carol"), value.commitments[0].bidder);
+
+        // settles after the reveal phase
+        let close = ExecuteMsg::CloseAuction {
+            auction_id: 1,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), close.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionNotEnded {}));
+        env.block.height = 21000;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), reveal(1500, "salt")).unwrap_err();
+        assert!(matches!(err, ContractError::RevealClosed {}));
+
+        let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), close).unwrap();
+        // alice pays bob's bid, carol and dave forfeit to the seller
+        assert!(res.attributes.contains(&attr("forfeited", "2500")));
+        assert_eq!(coins(2000, "uusd"), claimable(deps.as_ref(), "alice"));
+        assert_eq!(coins(2000, "uusd"), claimable(deps.as_ref(), "bob"));
+        assert_eq!(coins(4500, "uusd"), claimable(deps.as_ref(), "seller"));
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "alice".to_string(),
+            token_id: "ID1".to_string(),
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(1u128), value.balance);
+    }
+
+    #[test]
+    fn bidder_cap() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 1)]);
+
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(10, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(20000),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: Some(AuctionKind::Sealed(SealedAuction {
+                reveal_close: Expiration::AtHeight(21000),
+                pricing: SealedPricing::FirstPrice {},
+                penalty: CommitmentPenalty::Refund {},
+            })),
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        // settlement stays bounded, late bidders are turned away
+        let commit = |bidder: &str| ExecuteMsg::CommitBid {
+            auction_id: 1,
+            commitment: bid_commitment(bidder, Uint128::new(10), "salt"),
+        };
+        for i in 0..MAX_BIDDERS {
+            let bidder = format!("bidder{}", i);
+            execute(deps.as_mut(), mock_env(), mock_info(&bidder, &coins(10, "uusd")), commit(&bidder)).unwrap();
+        }
+        let err = execute(deps.as_mut(), mock_env(), mock_info("late", &coins(10, "uusd")), commit("late")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+
+        let msg = ExecuteMsg::Mint {
+            to: "seller".to_string(),
+            token_id: "ID2".to_string(),
+            value: Uint128::new(5u128),
+            msg: None,
+            royalty: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap();
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID2".to_string(),
+            amount: Uint128::new(5u128),
+            reserve_price: coin(10, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(20000),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: Some(AuctionKind::MultiUnit {}),
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        let bid_units = |unit_price: u128| ExecuteMsg::BidUnits {
+            auction_id: 2,
+            quantity: Uint128::new(1u128),
+            unit_price: Uint128::new(unit_price),
+        };
+        for i in 0..MAX_BIDDERS {
+            let bidder = format!("bidder{}", i);
+            execute(deps.as_mut(), mock_env(), mock_info(&bidder, &coins(10, "uusd")), bid_units(10)).unwrap();
+        }
+        let err = execute(deps.as_mut(), mock_env(), mock_info("late", &coins(10, "uusd")), bid_units(10)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        // bidders already in may still replace their bid
+        execute(deps.as_mut(), mock_env(), mock_info("bidder0", &coins(20, "uusd")), bid_units(20)).unwrap();
+    }
+
+    #[test]
+    fn multi_unit_uniform_price() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 5)]);
+
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(5u128),
+            reserve_price: coin(100, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: Some(AuctionKind::MultiUnit {}),
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        let bid = |quantity: u128, unit_price: u128| ExecuteMsg::BidUnits {
+            auction_id: 1,
+            quantity: Uint128::new(quantity),
+            unit_price: Uint128::new(unit_price),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(989, "uusd")), bid(3, 330)).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFundsSend {}));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(600, "uusd")), bid(6, 100)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(900, "uusd")), bid(3, 300)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(300, "uusd")), bid(3, 100)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &coins(200, "uusd")), bid(1, 200)).unwrap();
+        // replacing a bid refunds the earlier one
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(750, "uusd")), bid(3, 250)).unwrap();
+        assert_eq!(coins(300, "uusd"), claimable(deps.as_ref(), "bob"));
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::UnitBids {
+            auction_id: 1,
+            start_after: None,
+            limit: None,
+        }).unwrap();
+        let value: UnitBidsResponse = from_binary(&res).unwrap();
+        assert_eq!(3, value.bids.len());
+
+        let mut env = mock_env();
+        env.block.height = 23123;
+        let msg = ExecuteMsg::CloseAuction {
+            auction_id: 1,
+        };
+        let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+        // alice fills 3, bob 2 of 3 and sets the price, carol misses out
+        assert!(res.attributes.contains(&attr("clearing_price", "250")));
+        assert_eq!(coins(150, "uusd"), claimable(deps.as_ref(), "alice"));
+        assert_eq!(coins(550, "uusd"), claimable(deps.as_ref(), "bob"));
+        assert_eq!(coins(200, "uusd"), claimable(deps.as_ref(), "carol"));
+        assert_eq!(coins(1250, "uusd"), claimable(deps.as_ref(), "seller"));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), ExecuteMsg::WithdrawAll {}).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "bob".to_string(),
+                amount: coins(550, "uusd"),
+            })]
+        );
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), ExecuteMsg::WithdrawAll {}).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw {}));
+        for (owner, balance) in [("alice", 3u128), ("bob", 2u128), ("carol", 0u128)].iter() {
+            let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+                owner: owner.to_string(),
+                token_id: "ID1".to_string(),
+            }).unwrap();
+            let value: BalanceResponse = from_binary(&res).unwrap();
+            assert_eq!(Uint128::new(*balance), value.balance);
+        }
+    }
+
+    #[test]
+    fn close_without_bids_returns_tokens() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 1)]);
+
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+
+        let mut env = mock_env();
+        env.block.height = 23123;
+        let msg = ExecuteMsg::CloseAuction {
+            auction_id: 1,
+        };
+        let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+        // nothing to pay out
+        assert_eq!(0, res.messages.len());
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "seller".to_string(),
+            token_id: "ID1".to_string(),
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(1u128), value.balance);
+        assert_eq!(Uint128::zero(), value.escrowed);
+    }
+
+    #[test]
+    fn auction_status_lifecycle() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 2)]);
+
+        let create = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create).unwrap();
+        let bid = ExecuteMsg::Bid { auction_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uusd")), bid.clone()).unwrap();
+
+        let status = |deps: Deps, env: Env, auction_id: u64| -> AuctionStatus {
+            let res = query(deps, env, QueryMsg::Auction { auction_id }).unwrap();
+            let value: AuctionResponse = from_binary(&res).unwrap();
+            value.status
+        };
+        assert_eq!(AuctionStatus::Active {}, status(deps.as_ref(), mock_env(), 1));
+
+        // ended once bidding closes, settled after closing
+        let mut env = mock_env();
+        env.block.height = 23123;
+        assert_eq!(AuctionStatus::Ended {}, status(deps.as_ref(), env.clone(), 1));
+        let err = execute(deps.as_mut(), env.clone(), mock_info("bidder", &coins(2000, "uusd")), bid.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionEnded {}));
+        let close = ExecuteMsg::CloseAuction { auction_id: 1 };
+        execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), close.clone()).unwrap();
+        assert_eq!(AuctionStatus::Settled {}, status(deps.as_ref(), env.clone(), 1));
+
+        // settled auctions cannot be closed, cancelled or bid on again
+        let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), close).unwrap_err();
+        match err {
+            ContractError::InvalidAuction { reason } => assert_eq!("auction is settled", reason),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        let cancel = ExecuteMsg::CancelAuction { auction_id: 1 };
+        let err = execute(deps.as_mut(), env, mock_info("seller", &[]), cancel).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(2000, "uusd")), bid).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+
+        // cancelled
+        let cancel = ExecuteMsg::CancelAuction { auction_id: 2 };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), cancel).unwrap();
+        assert_eq!(AuctionStatus::Cancelled {}, status(deps.as_ref(), mock_env(), 2));
+        let bid = ExecuteMsg::Bid { auction_id: 2 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uusd")), bid).unwrap_err();
+        match err {
+            ContractError::InvalidAuction { reason } => assert_eq!("auction is cancelled", reason),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn scheduled_bidding_start() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 1)]);
+
+        let create = |bidding_start: Expiration| ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: Some(bidding_start),
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+
+        // start must come first, in the unit of the close
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(Expiration::AtHeight(23123))).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        let start_time = Expiration::AtTime(mock_env().block.time.plus_seconds(60));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(start_time)).unwrap_err();
+        match err {
+            ContractError::InvalidAuction { reason } => assert_eq!("bidding_start must match the unit of bidding_close", reason),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        // and bidding has to close
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: Some(Expiration::AtHeight(20000)),
+            bidding_close: Expiration::Never {},
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(Expiration::AtHeight(20000))).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Auction { auction_id: 1 }).unwrap();
+        let value: AuctionResponse = from_binary(&res).unwrap();
+        assert_eq!(AuctionStatus::Scheduled {}, value.status);
+
+        let bid = ExecuteMsg::Bid { auction_id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uusd")), bid.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::AuctionNotStarted {}));
+
+        let mut env = mock_env();
+        env.block.height = 20000;
+        execute(deps.as_mut(), env, mock_info("bidder", &coins(1000, "uusd")), bid).unwrap();
+    }
+
+    #[test]
+    fn cancel_auction() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 2)]);
+
+        let create = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(2u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create.clone()).unwrap();
+
+        let cancel = ExecuteMsg::CancelAuction {
+            auction_id: 1,
+        };
+        // only the seller or an operator
+        let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), cancel.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), cancel).unwrap();
+        assert!(res.attributes.contains(&attr("action", "cancel_auction")));
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "seller".to_string(),
+            token_id: "ID1".to_string(),
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(2u128), value.balance);
+        assert_eq!(Uint128::zero(), value.escrowed);
+
+        // the token can be relisted, but not cancelled once bid on
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create).unwrap();
+        let bid = ExecuteMsg::Bid {
+            auction_id: 2,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uusd")), bid).unwrap();
+        let cancel = ExecuteMsg::CancelAuction {
+            auction_id: 2,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), cancel).unwrap_err();
+        match err {
+            ContractError::InvalidAuction { reason } => assert_eq!("auction already has bids", reason),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn receive_creates_auction() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("seller", "ID1", 5)]);
+
+        // list local tokens by sending them to the contract
+        let payload = to_binary(&ReceiveMsg::CreateAuction {
+            reserve_price: coin(1000, "uusd"),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        }).unwrap();
+        let msg = ExecuteMsg::SendFrom {
+            from: "seller".to_string(),
+            to: MOCK_CONTRACT_ADDR.to_string(),
+            token_id: "ID1".to_string(),
+            value: Uint128::new(2u128),
+            msg: Some(payload.clone()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        // the contract receives its own hook
+        let msg = ExecuteMsg::Receive(Cw1155ReceiveMsg {
+            operator: "seller".to_string(),
+            from: Some("seller".to_string()),
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(2u128),
+            msg: payload.clone(),
+        }.into());
+        execute(deps.as_mut(), mock_env(), mock_info(MOCK_CONTRACT_ADDR, &[]), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "seller".to_string(),
+            token_id: "ID1".to_string(),
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(3u128), value.balance);
+        assert_eq!(Uint128::new(2u128), value.escrowed);
 
-    guard_can_approve(deps.as_ref(), &env, &from_addr, &info.sender)?;
+        // list tokens of an external collection
+        let msg = ExecuteMsg::BatchReceive(Cw1155BatchReceiveMsg {
+            operator: "other".to_string(),
+            from: Some("other".to_string()),
+            batch: vec![("EXT1".to_string(), Uint128::new(1u128))],
+            msg: payload,
+        });
+        let res = execute(deps.as_mut(), mock_env(), mock_info("collection", &[]), msg).unwrap();
+        assert_eq!(vec![2u64], from_binary::<Vec<u64>>(&res.data.unwrap()).unwrap());
 
-    let mut rsp = Response::default();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Auction {
+            auction_id: 2,
+        }).unwrap();
+        let value: AuctionResponse = from_binary(&res).unwrap();
+        assert_eq!(Addr::unchecked("collection"), value.auction.collection);
 
-    let event = execute_transfer_inner(
-        &mut deps,
-        Some(&from_addr),
-        Some(&to_addr),
-        &token_id,
-        amount,
-    )?;
-    event.add_attributes(&mut rsp);
+        // settlement sends the tokens out through the external collection
+        let msg = ExecuteMsg::CloseAuction {
+            auction_id: 2,
+        };
+        let mut env = mock_env();
+        env.block.height = 23123;
+        let res = execute(deps.as_mut(), env, mock_info("other", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::reply_on_error(WasmMsg::Execute {
+                contract_addr: "collection".to_string(),
+                msg: to_binary(&ExecuteMsg::SendFrom {
+                    from: MOCK_CONTRACT_ADDR.to_string(),
+                    to: "other".to_string(),
+                    token_id: "EXT1".to_string(),
+                    value: Uint128::new(1u128),
+                    msg: None,
+                }).unwrap(),
+                funds: vec![],
+            }, 0)
+        );
 
-    if let Some(msg) = msg {
-        rsp.messages = vec![SubMsg::new(
-            Cw1155ReceiveMsg {
-                operator: info.sender.to_string(),
-                from: Some(from),
-                amount,
-                token_id: token_id.clone(),
-                msg,
-            }
-            .into_cosmos_msg(to)?,
-        )]
+        // the seller is paid once the tokens went out, the buyer refunded if they did not
+        let msg = ExecuteMsg::BatchReceive(Cw1155BatchReceiveMsg {
+            operator: "other".to_string(),
+            from: Some("other".to_string()),
+            batch: vec![("EXT2".to_string(), Uint128::new(1u128)), ("EXT3".to_string(), Uint128::new(1u128))],
+            msg: to_binary(&ReceiveMsg::CreateAuction {
+                reserve_price: coin(1000, "uusd"),
+                bidding_start: None,
+                bidding_close: Expiration::AtHeight(23123),
+                min_increment: None,
+                anti_snipe: None,
+                buy_now_price: None,
+                kind: None,
+                payees: None,
+            }).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("collection", &[]), msg).unwrap();
+        for auction_id in [3, 4] {
+            let msg = ExecuteMsg::Bid { auction_id };
+            execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uusd")), msg).unwrap();
+        }
+        let mut env = mock_env();
+        env.block.height = 23123;
+        for (auction_id, reply_id) in [(3, 1), (4, 2)] {
+            let msg = ExecuteMsg::CloseAuction { auction_id };
+            let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+            assert_eq!(ReplyOn::Always, res.messages[0].reply_on);
+            assert_eq!(reply_id, res.messages[0].id);
+        }
+        assert!(claimable(deps.as_ref(), "other").is_empty());
+        let sent = Reply {
+            id: 1,
+            result: ContractResult::Ok(SubMsgExecutionResponse { events: vec![], data: None }),
+        };
+        reply(deps.as_mut(), env.clone(), sent).unwrap();
+        assert_eq!(coins(1000, "uusd"), claimable(deps.as_ref(), "other"));
+        let failed = Reply { id: 2, result: ContractResult::Err("rejected".to_string()) };
+        let res = reply(deps.as_mut(), env, failed).unwrap();
+        assert!(res.attributes.contains(&attr("transfer_failed", "rejected")));
+        assert_eq!(coins(1000, "uusd"), claimable(deps.as_ref(), "bidder"));
+        assert_eq!(coins(1000, "uusd"), claimable(deps.as_ref(), "other"));
     }
-    Ok(rsp)
-}
 
-pub fn execute_approve_all(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    operator: String,
-    expires: Option<Expiration>,
-) -> Result<Response, ContractError> {
+    #[test]
+    fn cw20_auction() {
+        let mut deps = mock_dependencies(&[]);
 
-    // reject expired data as invalid
-    let expires = expires.unwrap_or_default();
-    if expires.is_expired(&env.block) {
-        return Err(ContractError::Expired {});
-    }
+        let msg = InstantiateMsg { cw20_whitelist: Some(vec!["token".to_string()]), ..instantiate_msg() };
+        setup(deps.as_mut(), msg, &[("seller", "ID1", 1)]);
 
-    // set the operator for us
-    let operator_addr = deps.api.addr_validate(&operator)?;
-    APPROVES.save(deps.storage, (&info.sender, &operator_addr), &expires)?;
+        // priced in the whitelisted token by its address
+        let msg = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "token"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
 
-    let mut rsp = Response::default();
-    ApproveAllEvent {
-        sender: info.sender.as_ref(),
-        operator: &operator,
-        approved: true,
-    }
-    .add_attributes(&mut rsp);
-    Ok(rsp)
-}
+        let send = |sender: &str, amount: u128| ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&Cw20HookMsg::Bid { auction_id: 1 }).unwrap(),
+        }.into());
+        // the hook arrives under the same key as the cw1155 one
+        let raw = br#"{"receive":{"sender":"bidder1","amount":"1000","msg":"eyJiaWQiOnsiYXVjdGlvbl9pZCI6MX19"}}"#;
+        assert_eq!(send("bidder1", 1000), cosmwasm_std::from_slice::<ExecuteMsg>(raw).unwrap());
 
-pub fn execute_revoke_all(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    operator: String
-) -> Result<Response, ContractError> {
-    let operator_addr = deps.api.addr_validate(&operator)?;
-    APPROVES.remove(deps.storage, (&info.sender, &operator_addr));
+        // other tokens and native coins do not count
+        let err = execute(deps.as_mut(), mock_env(), mock_info("other", &[]), send("bidder1", 1000)).unwrap_err();
+        assert!(matches!(err, ContractError::UnexpectedFunds { .. }));
+        let bid = ExecuteMsg::Bid { auction_id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder1", &coins(1000, "uusd")), bid).unwrap_err();
+        assert!(matches!(err, ContractError::UnexpectedFunds { .. }));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("token", &coins(1000, "uusd")), send("bidder1", 1000)).unwrap_err();
+        assert!(matches!(err, ContractError::UnexpectedFunds { .. }));
 
-    let mut rsp = Response::default();
-    ApproveAllEvent {
-        sender: info.sender.as_ref(),
-        operator: &operator,
-        approved: false,
-    }
-    .add_attributes(&mut rsp);
-    Ok(rsp)
-}
+        execute(deps.as_mut(), mock_env(), mock_info("token", &[]), send("bidder1", 1000)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("token", &[]), send("bidder2", 1500)).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Claimable { address: "bidder1".to_string() }).unwrap();
+        let value: ClaimableResponse = from_binary(&res).unwrap();
+        assert!(value.claimable.is_empty());
+        assert_eq!(vec![Cw20CoinVerified { address: Addr::unchecked("token"), amount: Uint128::new(1000) }], value.cw20);
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetCount {} => to_binary(&query_count(deps)?),
-        QueryMsg::Balance { owner, token_id } => {
-            let owner_addr = deps.api.addr_validate(&owner)?;
-            let balance = BALANCES
-                .may_load(deps.storage, (&owner_addr, &token_id))?
-                .unwrap_or_default();
-            to_binary(&BalanceResponse { balance })
-        },
-        QueryMsg::IsApprovedForAll { owner, operator } => {
-            let owner_addr = deps.api.addr_validate(&owner)?;
-            let operator_addr = deps.api.addr_validate(&operator)?;
-            let approved = check_can_approve(deps, &env, &owner_addr, &operator_addr)?;
-            to_binary(&IsApprovedForAllResponse { approved })
-        },
-        QueryMsg::Auction { seller, token_id } => {
-            let seller_addr = deps.api.addr_validate(&seller)?;
-            let auction = AUCTIONS
-                .may_load(deps.storage, (&seller_addr, &token_id))?;
-            match auction {
-                None => {
-                    return Err(StdError::NotFound { kind: "invalid auction".to_string() });
-                }
-                Some(auction_val) => { return to_binary(&AuctionResponse { auction: auction_val }) }
-            }
-            
-        },
+        // refunds go out as token transfers, withdrawing takes no funds
+        let withdraw = ExecuteMsg::Withdraw { denom: "token".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder1", &coins(1, "uusd")), withdraw.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::UnexpectedFunds { .. }));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bidder1", &[]), withdraw).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "bidder1".to_string(),
+                    amount: Uint128::new(1000),
+                }).unwrap(),
+                funds: vec![],
+            })]
+        );
+
+        // and so do the proceeds of the seller
+        let mut env = mock_env();
+        env.block.height = 23123;
+        execute(deps.as_mut(), env, mock_info("anyone", &[]), ExecuteMsg::CloseAuction { auction_id: 1 }).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), ExecuteMsg::WithdrawAll {}).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "seller".to_string(),
+                    amount: Uint128::new(1500),
+                }).unwrap(),
+                funds: vec![],
+            })]
+        );
     }
-}
 
-fn query_count(deps: Deps) -> StdResult<CountResponse> {
-    let state = STATE.load(deps.storage)?;
-    Ok(CountResponse { count: state.count })
-}
+    #[test]
+    fn accepted_denoms() {
+        let mut deps = mock_dependencies(&[]);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coin, coins, from_binary};
+        let msg = InstantiateMsg { accepted_denoms: Some(vec!["uusd".to_string()]), ..instantiate_msg() };
+        setup(deps.as_mut(), msg, &[("seller", "ID1", 2)]);
+
+        let create = |denom: &str| ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, denom),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create("uluna")).unwrap_err();
+        assert!(matches!(err, ContractError::DenomNotAccepted { .. }));
+
+        // only the owner updates the lists
+        let update = ExecuteMsg::UpdateAcceptedDenoms { add: vec!["uluna".to_string()], remove: vec!["uusd".to_string()] };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), update.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), update).unwrap();
+        let update = ExecuteMsg::UpdateCw20Whitelist { add: vec!["token".to_string()], remove: vec![] };
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), update).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let value: Config = from_binary(&res).unwrap();
+        assert_eq!(vec!["uluna".to_string()], value.accepted_denoms);
+        assert_eq!(vec![Addr::unchecked("token")], value.cw20_whitelist);
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create("uusd")).unwrap_err();
+        assert!(matches!(err, ContractError::DenomNotAccepted { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create("uluna")).unwrap();
+
+        // bids pay in exactly the auction denom
+        let bid = ExecuteMsg::Bid { auction_id: 1 };
+        let funds = vec![coin(1000, "uluna"), coin(1, "uusd")];
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bidder", &funds), bid.clone()).unwrap_err();
+        match err {
+            ContractError::UnexpectedFunds { denom } => assert_eq!("uusd", denom),
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uluna")), bid).unwrap();
+    }
 
     #[test]
-    fn proper_initialization() {
+    fn protocol_fee() {
         let mut deps = mock_dependencies(&[]);
 
-        let msg = InstantiateMsg { count: 17, minter: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string() };
-        let info = mock_info("creator", &coins(1000, "ust"));
+        let msg = InstantiateMsg { protocol_fee_bps: Some(10_001), treasury: Some("treasury".to_string()), ..instantiate_msg() };
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProtocolFee {}));
+        let msg = InstantiateMsg { protocol_fee_bps: Some(250), ..msg };
+        setup(deps.as_mut(), msg, &[("seller", "ID1", 2)]);
 
-        // we can just call .unwrap() to assert this was a success
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+        let create = ExecuteMsg::CreateAuction {
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create.clone()).unwrap();
+        let bid = ExecuteMsg::Bid { auction_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1001, "uusd")), bid).unwrap();
 
-        // it worked, let's query the state
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-        let value: CountResponse = from_binary(&res).unwrap();
-        assert_eq!(17, value.count);
+        // 2.5% of 1001 rounds down to 25
+        let mut env = mock_env();
+        env.block.height = 23123;
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), ExecuteMsg::CloseAuction { auction_id: 1 }).unwrap();
+        assert!(res.attributes.contains(&attr("protocol_fee", "25")));
+        assert!(res.attributes.contains(&attr("seller_proceeds", "976")));
+        assert_eq!(coins(25, "uusd"), claimable(deps.as_ref(), "treasury"));
+        assert_eq!(coins(976, "uusd"), claimable(deps.as_ref(), "seller"));
+
+        // only the owner changes the fee, for later settlements
+        let update = ExecuteMsg::UpdateProtocolFee { protocol_fee_bps: 0, treasury: "treasury".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), update.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), update).unwrap();
+
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create).unwrap();
+        let bid = ExecuteMsg::Bid { auction_id: 2 };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uusd")), bid).unwrap();
+        let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), ExecuteMsg::CloseAuction { auction_id: 2 }).unwrap();
+        assert!(res.attributes.contains(&attr("protocol_fee", "0")));
+        assert_eq!(coins(25, "uusd"), claimable(deps.as_ref(), "treasury"));
+        assert_eq!(coins(1976, "uusd"), claimable(deps.as_ref(), "seller"));
     }
-    /// Instantiate
-    /// Mint Token
-    /// Create Auction
-    /// Bid Auction
-    /// Close Auction
+
     #[test]
-    fn auction() {
+    fn royalties() {
         let mut deps = mock_dependencies(&[]);
 
-        let msg = InstantiateMsg { count: 17, minter: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string() };
-        let info = mock_info("creator", &coins(1000, "uusd"));
-
-        // we can just call .unwrap() to assert this was a success
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+        let msg = InstantiateMsg { protocol_fee_bps: Some(250), treasury: Some("treasury".to_string()), ..instantiate_msg() };
+        setup(deps.as_mut(), msg, &[]);
 
-        // mint token
-        // ===================
-        let info = mock_info("terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8", &coins(1000, "uusd"));
         let msg = ExecuteMsg::Mint {
-            to: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
+            to: "seller".to_string(),
             token_id: "ID1".to_string(),
-            value: Uint128::new(10001u128),
+            value: Uint128::new(1u128),
             msg: None,
+            royalty: Some(RoyaltyMsg { recipient: "artist".to_string(), bps: 10_001 }),
         };
-        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRoyalty {}));
+        let msg = ExecuteMsg::Mint {
+            to: "seller".to_string(),
+            token_id: "ID1".to_string(),
+            value: Uint128::new(1u128),
+            msg: None,
+            royalty: Some(RoyaltyMsg { recipient: "artist".to_string(), bps: 1000 }),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), msg).unwrap();
 
-        // query nft token
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
-            owner: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
-            token_id: "ID1".to_string()
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::RoyaltyInfo {
+            token_id: "ID1".to_string(),
+            sale_price: Uint128::new(1000),
         }).unwrap();
-        let value: BalanceResponse = from_binary(&res).unwrap();
-        assert_eq!(Uint128::new(10001u128), value.balance);
+        let value: RoyaltiesInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(RoyaltiesInfoResponse { address: "artist".to_string(), royalty_amount: Uint128::new(100) }, value);
 
-        // create auction
-        // ===================
-        let info = mock_info("terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8", &coins(1000, "uusd"));
         let msg = ExecuteMsg::CreateAuction {
             token_id: "ID1".to_string(),
             amount: Uint128::new(1u128),
-            price: coin(1000, "uusd"),
-            seller: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
             bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: None,
         };
-        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+        let bid = ExecuteMsg::Bid { auction_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uusd")), bid).unwrap();
 
-        // query auction
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::Auction {
-            seller: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
-            token_id: "ID1".to_string()
+        // the royalty comes out of the sale next to the protocol fee
+        let mut env = mock_env();
+        env.block.height = 23123;
+        let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), ExecuteMsg::CloseAuction { auction_id: 1 }).unwrap();
+        assert!(res.attributes.contains(&attr("royalty", "100")));
+        assert!(res.attributes.contains(&attr("seller_proceeds", "875")));
+        assert_eq!(coins(25, "uusd"), claimable(deps.as_ref(), "treasury"));
+        assert_eq!(coins(100, "uusd"), claimable(deps.as_ref(), "artist"));
+        assert_eq!(coins(875, "uusd"), claimable(deps.as_ref(), "seller"));
+
+        // only the minter updates royalties
+        let update = ExecuteMsg::UpdateRoyalty { token_id: "ID1".to_string(), royalty: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("artist", &[]), update.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("minter", &[]), update).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::RoyaltyInfo {
+            token_id: "ID1".to_string(),
+            sale_price: Uint128::new(1000),
         }).unwrap();
-        let value: AuctionResponse = from_binary(&res).unwrap();
-        assert_eq!(Uint128::new(1u128), value.auction.amount);
-        assert_eq!(coin(1000, "uusd"), value.auction.price);
-        assert_eq!(Expiration::AtHeight(23123), value.auction.bidding_close);
+        let value: RoyaltiesInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::zero(), value.royalty_amount);
+    }
 
-        // place bid
-        // ===================
-        let info = mock_info("terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd9", &coins(10000, "uusd"));
-        let msg = ExecuteMsg::Bid {
+    #[test]
+    fn split_proceeds() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg { protocol_fee_bps: Some(100), treasury: Some("treasury".to_string()), ..instantiate_msg() };
+        setup(deps.as_mut(), msg, &[("seller", "ID1", 1)]);
+
+        let create = |payees: Vec<(&str, u64)>| ExecuteMsg::CreateAuction {
             token_id: "ID1".to_string(),
-            seller: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
+            amount: Uint128::new(1u128),
+            reserve_price: coin(1000, "uusd"),
+            seller: "seller".to_string(),
+            bidding_start: None,
+            bidding_close: Expiration::AtHeight(23123),
+            min_increment: None,
+            anti_snipe: None,
+            buy_now_price: None,
+            kind: None,
+            payees: Some(payees
+                .into_iter()
+                .map(|(address, share_bps)| PayeeMsg { address: address.to_string(), share_bps })
+                .collect()),
         };
-        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // shares must be positive and make up the whole
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(vec![("alice", 5000), ("bob", 4999)])).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(vec![("alice", 10_000), ("bob", 0)])).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(vec![])).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAuction { .. }));
+        let payees = vec![("alice", 3334), ("bob", 3333), ("carol", 3333)];
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), create(payees)).unwrap();
 
-        // query auction
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::Auction {
-            seller: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
-            token_id: "ID1".to_string()
+        let bid = ExecuteMsg::Bid { auction_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &coins(1000, "uusd")), bid).unwrap();
+        let mut env = mock_env();
+        env.block.height = 23123;
+        execute(deps.as_mut(), env, mock_info("anyone", &[]), ExecuteMsg::CloseAuction { auction_id: 1 }).unwrap();
+
+        // 990 after the fee splits into 330, 329 and 329 rounded down, the dust of 2 goes to alice
+        assert_eq!(coins(10, "uusd"), claimable(deps.as_ref(), "treasury"));
+        assert_eq!(coins(332, "uusd"), claimable(deps.as_ref(), "alice"));
+        assert_eq!(coins(329, "uusd"), claimable(deps.as_ref(), "bob"));
+        assert_eq!(coins(329, "uusd"), claimable(deps.as_ref(), "carol"));
+        assert!(claimable(deps.as_ref(), "seller").is_empty());
+    }
+
+    #[test]
+    fn fixed_price_listings() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg { protocol_fee_bps: Some(100), treasury: Some("treasury".to_string()), ..instantiate_msg() };
+        setup(deps.as_mut(), msg, &[("seller", "ID1", 10), ("seller", "ID2", 10)]);
+
+        let list = |token_id: &str, amount: u128, unit_price: Coin| ExecuteMsg::ListForSale {
+            seller: "seller".to_string(),
+            token_id: token_id.to_string(),
+            amount: Uint128::new(amount),
+            unit_price,
+        };
+        // only the seller or an operator lists
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &[]), list("ID1", 5, coin(100, "uusd"))).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), list("ID1", 5, coin(100, "ukrw"))).unwrap_err();
+        assert!(matches!(err, ContractError::DenomNotAccepted { .. }));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), list("ID1", 11, coin(100, "uusd"))).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientNftBalance {}));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), list("ID1", 5, coin(100, "uusd"))).unwrap();
+        assert_eq!(1u64, from_binary::<u64>(&res.data.unwrap()).unwrap());
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), list("ID2", 1, coin(500, "uusd"))).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "seller".to_string(),
+            token_id: "ID1".to_string(),
         }).unwrap();
-        let value: AuctionResponse = from_binary(&res).unwrap();
-        assert_eq!(coin(10000, "uusd"), value.auction.price);
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(5u128), value.escrowed);
 
-        // close auction
-        // ===================
-        let info = mock_info("terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8", &coins(10000, "uusd"));
-        let msg = ExecuteMsg::CloseAuction {
+        // partial purchases take units off the listing
+        let buy = |amount: u128| ExecuteMsg::Buy { listing_id: 1, amount: Uint128::new(amount) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(299, "uusd")), buy(3)).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFundsSend {}));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(350, "uusd")), buy(3)).unwrap();
+        assert!(res.attributes.contains(&attr("protocol_fee", "3")));
+        assert_eq!(coins(297, "uusd"), claimable(deps.as_ref(), "seller"));
+        assert_eq!(coins(50, "uusd"), claimable(deps.as_ref(), "buyer"));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(300, "uusd")), buy(3)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidListing { .. }));
+
+        let ids = |deps: Deps, msg: QueryMsg| -> Vec<(u64, u128)> {
+            let value: ListingsResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            value.listings.iter().map(|listing| (listing.listing_id, listing.listing.amount.u128())).collect()
+        };
+        assert_eq!(vec![(1, 2), (2, 1)], ids(deps.as_ref(), QueryMsg::ListListings { start_after: None, limit: None }));
+        assert_eq!(vec![(2, 1)], ids(deps.as_ref(), QueryMsg::ListingsBySeller { seller: "seller".to_string(), start_after: Some(1), limit: None }));
+        assert_eq!(vec![(1, 2)], ids(deps.as_ref(), QueryMsg::ListingsByToken { token_id: "ID1".to_string(), start_after: None, limit: None }));
+
+        // buying the rest removes the listing
+        execute(deps.as_mut(), mock_env(), mock_info("buyer", &coins(200, "uusd")), buy(2)).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "buyer".to_string(),
             token_id: "ID1".to_string(),
-            seller: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(),
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(5u128), value.balance);
+
+        // only the seller or an operator delists
+        let err = execute(deps.as_mut(), mock_env(), mock_info("buyer", &[]), ExecuteMsg::Delist { listing_id: 2 }).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), ExecuteMsg::Delist { listing_id: 2 }).unwrap();
+        assert!(ids(deps.as_ref(), QueryMsg::ListListings { start_after: None, limit: None }).is_empty());
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "seller".to_string(),
+            token_id: "ID2".to_string(),
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(10u128), value.balance);
+        assert_eq!(Uint128::zero(), value.escrowed);
+
+        // an approved operator lists the seller's tokens
+        let msg = ExecuteMsg::ApproveAll { operator: "operator".to_string(), expires: None };
+        execute(deps.as_mut(), mock_env(), mock_info("seller", &[]), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), list("ID2", 4, coin(500, "uusd"))).unwrap();
+        assert_eq!(vec![(3, 4)], ids(deps.as_ref(), QueryMsg::ListingsBySeller { seller: "seller".to_string(), start_after: None, limit: None }));
+    }
+
+    #[test]
+    fn offers_on_held_tokens() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("holder", "ID1", 3)]);
+
+        let height = mock_env().block.height;
+        let offer = |amount: u128, expires: Expiration| ExecuteMsg::MakeOffer {
+            owner: "holder".to_string(),
+            token_id: "ID1".to_string(),
+            amount: Uint128::new(amount),
+            expires,
         };
-        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // the holder must have the units, the offer must not have expired
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(100, "uusd")), offer(4, Expiration::AtHeight(height + 10))).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientNftBalance {}));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(100, "uusd")), offer(2, Expiration::AtHeight(height))).unwrap_err();
+        assert!(matches!(err, ContractError::Expired {}));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), offer(2, Expiration::AtHeight(height + 10))).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFundsSend {}));
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(100, "uusd")), offer(2, Expiration::AtHeight(height + 10))).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(50, "uusd")), offer(1, Expiration::AtHeight(height + 10))).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(10, "uusd")), offer(1, Expiration::AtHeight(height + 10))).unwrap();
 
-        // query nft token
+        let ids = |deps: Deps, msg: QueryMsg| -> Vec<u64> {
+            let value: OffersResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            value.offers.iter().map(|offer| offer.offer_id).collect()
+        };
+        assert_eq!(vec![1, 2, 3], ids(deps.as_ref(), QueryMsg::OffersByToken { token_id: "ID1".to_string(), start_after: None, limit: None }));
+        assert_eq!(vec![3], ids(deps.as_ref(), QueryMsg::OffersByOfferer { offerer: "alice".to_string(), start_after: Some(1), limit: None }));
+
+        // only the holder or an operator accepts
+        let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), ExecuteMsg::AcceptOffer { offer_id: 1 }).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let msg = ExecuteMsg::ApproveAll { operator: "operator".to_string(), expires: None };
+        execute(deps.as_mut(), mock_env(), mock_info("holder", &[]), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("operator", &[]), ExecuteMsg::AcceptOffer { offer_id: 1 }).unwrap();
+        assert_eq!(coins(100, "uusd"), claimable(deps.as_ref(), "holder"));
         let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
-            owner: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd9".to_string(),
-            token_id: "ID1".to_string()
+            owner: "alice".to_string(),
+            token_id: "ID1".to_string(),
         }).unwrap();
         let value: BalanceResponse = from_binary(&res).unwrap();
-        assert_eq!(Uint128::new(1u128), value.balance);
+        assert_eq!(Uint128::new(2u128), value.balance);
+
+        // the offerer withdraws at any time, anyone refunds once expired
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::WithdrawOffer { offer_id: 3 }).unwrap();
+        assert_eq!(coins(10, "uusd"), claimable(deps.as_ref(), "alice"));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("holder", &[]), ExecuteMsg::WithdrawOffer { offer_id: 2 }).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let mut env = mock_env();
+        env.block.height = height + 10;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("holder", &[]), ExecuteMsg::AcceptOffer { offer_id: 2 }).unwrap_err();
+        assert!(matches!(err, ContractError::Expired {}));
+        execute(deps.as_mut(), env, mock_info("holder", &[]), ExecuteMsg::WithdrawOffer { offer_id: 2 }).unwrap();
+        assert_eq!(coins(50, "uusd"), claimable(deps.as_ref(), "bob"));
+        assert!(ids(deps.as_ref(), QueryMsg::OffersByToken { token_id: "ID1".to_string(), start_after: None, limit: None }).is_empty());
+    }
+
+    #[test]
+    fn collection_offers_filled_by_any_matching_token() {
+        let mut deps = mock_dependencies(&[]);
+
+        setup(deps.as_mut(), instantiate_msg(), &[("holder", "punk-1", 2), ("holder", "punk-2", 2), ("other", "ape-1", 2)]);
+
+        let height = mock_env().block.height;
+        let offer = |scope: OfferScope, quantity: u128| ExecuteMsg::MakeCollectionOffer {
+            scope,
+            unit_price: coin(10, "uusd"),
+            quantity: Uint128::new(quantity),
+            expires: Expiration::AtHeight(height + 10),
+        };
+        let punks = || OfferScope::Prefix { prefix: "punk-".to_string() };
+        // funds must cover every unit, overpayment is claimable
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(20, "uusd")), offer(punks(), 3)).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFundsSend {}));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(30, "uusd")), offer(OfferScope::TokenIds { token_ids: vec![] }, 3)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidOffer { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &coins(35, "uusd")), offer(punks(), 3)).unwrap();
+        assert_eq!(coins(5, "uusd"), claimable(deps.as_ref(), "alice"));
+        let scope = OfferScope::TokenIds { token_ids: vec!["ape-1".to_string(), "punk-2".to_string()] };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &coins(10, "uusd")), offer(scope, 1)).unwrap();
+        let everything = OfferScope::Prefix { prefix: "".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info("carol", &coins(10, "uusd")), offer(everything, 1)).unwrap();
+
+        let ids = |deps: Deps, msg: QueryMsg| -> Vec<u64> {
+            let value: CollectionOffersResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            value.offers.iter().map(|offer| offer.offer_id).collect()
+        };
+        let for_token = |token_id: &str, start_after: Option<u64>, limit: Option<u32>| QueryMsg::CollectionOffersForToken {
+            token_id: token_id.to_string(),
+            start_after,
+            limit,
+        };
+        assert_eq!(vec![1, 2, 3], ids(deps.as_ref(), for_token("punk-2", None, None)));
+        assert_eq!(vec![2], ids(deps.as_ref(), for_token("punk-2", Some(1), Some(1))));
+        assert_eq!(vec![2, 3], ids(deps.as_ref(), for_token("ape-1", None, None)));
+        assert_eq!(vec![3], ids(deps.as_ref(), for_token("punk", None, None)));
+        assert_eq!(vec![2], ids(deps.as_ref(), QueryMsg::CollectionOffersByOfferer { offerer: "bob".to_string(), start_after: None, limit: None }));
+
+        let fill = |offer_id: u64, owner: &str, token_id: &str, amount: u128| ExecuteMsg::FillCollectionOffer {
+            offer_id,
+            owner: owner.to_string(),
+            token_id: token_id.to_string(),
+            amount: Uint128::new(amount),
+        };
+        // only matching tokens, filled by the holder or an operator
+        let err = execute(deps.as_mut(), mock_env(), mock_info("other", &[]), fill(1, "other", "ape-1", 1)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidOffer { .. }));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("other", &[]), fill(1, "holder", "punk-1", 1)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info("holder", &[]), fill(1, "holder", "punk-1", 2)).unwrap();
+        assert_eq!(coins(20, "uusd"), claimable(deps.as_ref(), "holder"));
+        let err = execute(deps.as_mut(), mock_env(), mock_info("holder", &[]), fill(1, "holder", "punk-2", 2)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidOffer { .. }));
+        execute(deps.as_mut(), mock_env(), mock_info("holder", &[]), fill(1, "holder", "punk-2", 1)).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Balance {
+            owner: "alice".to_string(),
+            token_id: "punk-1".to_string(),
+        }).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::new(2u128), value.balance);
+        // used up offers are removed
+        assert_eq!(vec![2, 3], ids(deps.as_ref(), QueryMsg::ListCollectionOffers { start_after: None, limit: None }));
+        assert_eq!(vec![2, 3], ids(deps.as_ref(), for_token("punk-2", None, None)));
+
+        // anyone refunds once expired
+        let err = execute(deps.as_mut(), mock_env(), mock_info("holder", &[]), ExecuteMsg::WithdrawCollectionOffer { offer_id: 2 }).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let mut env = mock_env();
+        env.block.height = height + 10;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("other", &[]), fill(2, "other", "ape-1", 1)).unwrap_err();
+        assert!(matches!(err, ContractError::Expired {}));
+        execute(deps.as_mut(), env.clone(), mock_info("holder", &[]), ExecuteMsg::WithdrawCollectionOffer { offer_id: 2 }).unwrap();
+        assert_eq!(coins(10, "uusd"), claimable(deps.as_ref(), "bob"));
+        assert_eq!(vec![3], ids(deps.as_ref(), for_token("ape-1", None, None)));
+        execute(deps.as_mut(), env, mock_info("holder", &[]), ExecuteMsg::WithdrawCollectionOffer { offer_id: 3 }).unwrap();
+        assert!(ids(deps.as_ref(), QueryMsg::ListCollectionOffers { start_after: None, limit: None }).is_empty());
+        assert!(ids(deps.as_ref(), for_token("punk-2", None, None)).is_empty());
     }
 
     #[test]
     fn increment() {
         let mut deps = mock_dependencies(&coins(2, "uusd"));
 
-        let msg = InstantiateMsg { count: 17, minter: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string() };
+        let msg = InstantiateMsg { minter: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(), ..instantiate_msg() };
         let info = mock_info("creator", &coins(2, "uusd"));
         let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -595,7 +4335,7 @@ mod tests {
     fn reset() {
         let mut deps = mock_dependencies(&coins(2, "uusd"));
 
-        let msg = InstantiateMsg { count: 17, minter: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string() };
+        let msg = InstantiateMsg { minter: "terra1dcegyrekltswvyy0xy69ydgxn9x8x32zdtapd8".to_string(), ..instantiate_msg() };
         let info = mock_info("creator", &coins(2, "uusd"));
         let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
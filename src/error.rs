@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +6,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -18,9 +21,45 @@ pub enum ContractError {
     #[error("Insufficient funds")]
     InsufficientFundsSend {},
 
-    #[error("Invalid auction")]
-    InvalidAuction {},
+    #[error("Unexpected funds sent in {denom}")]
+    UnexpectedFunds { denom: String },
+
+    #[error("Invalid auction: {reason}")]
+    InvalidAuction { reason: String },
+
+    #[error("Denom not accepted: {denom}")]
+    DenomNotAccepted { denom: String },
+
+    #[error("Invalid listing: {reason}")]
+    InvalidListing { reason: String },
+
+    #[error("Invalid offer: {reason}")]
+    InvalidOffer { reason: String },
+
+    #[error("Auction Not Started")]
+    AuctionNotStarted {},
 
     #[error("Auction Ended")]
     AuctionEnded {},
+
+    #[error("Auction Not Ended")]
+    AuctionNotEnded {},
+
+    #[error("Invalid bid increment")]
+    InvalidBidIncrement {},
+
+    #[error("Invalid protocol fee")]
+    InvalidProtocolFee {},
+
+    #[error("Invalid royalty")]
+    InvalidRoyalty {},
+
+    #[error("Reveal Closed")]
+    RevealClosed {},
+
+    #[error("Commitment does not match the revealed bid")]
+    InvalidCommitment {},
+
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw {},
 }
@@ -54,3 +54,21 @@ impl<'a> Event for ApproveAllEvent<'a> {
       .push(attr("approved", (self.approved as u32).to_string()));
   }
 }
+
+/// Tracks auctions withdrawn by the seller
+pub struct CancelAuctionEvent<'a> {
+  pub auction_id: u64,
+  pub seller: &'a str,
+  pub token_id: &'a str,
+  pub amount: Uint128,
+}
+
+impl<'a> Event for CancelAuctionEvent<'a> {
+  fn add_attributes(&self, rsp: &mut Response) {
+    rsp.attributes.push(attr("action", "cancel_auction"));
+    rsp.attributes.push(attr("auction_id", self.auction_id.to_string()));
+    rsp.attributes.push(attr("seller", self.seller));
+    rsp.attributes.push(attr("token_id", self.token_id));
+    rsp.attributes.push(attr("amount", self.amount));
+  }
+}
@@ -1,10 +1,15 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdResult, Uint128, WasmMsg, Coin};
+use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdError, StdResult, Uint128, WasmMsg, Coin};
 use cw0::{Expiration};
+use cw20::{Cw20CoinVerified, Cw20ReceiveMsg};
+use sha2::{Digest, Sha256};
 
-use crate::state::{Auction};
+use crate::state::{
+    Auction, AuctionKind, AuctionStatus, AntiSnipe, BidIncrement, BidRecord, CollectionOffer, Commitment, Listing, Offer,
+    OfferScope, UnitBid
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -13,21 +18,38 @@ pub struct InstantiateMsg {
     /// contract.
     pub minter: String,
     pub count: i32,
+    /// Minimum bid increment for auctions that do not set their own,
+    /// defaults to any amount above the highest bid.
+    pub default_min_increment: Option<BidIncrement>,
+    /// Native denoms auctions can be priced in, defaults to uluna and uusd.
+    pub accepted_denoms: Option<Vec<String>>,
+    /// CW20 tokens auctions can be priced in, by giving the token address
+    /// as the denom of the reserve price.
+    pub cw20_whitelist: Option<Vec<String>>,
+    /// Share of every sale paid to the treasury, defaults to none
+    pub protocol_fee_bps: Option<u64>,
+    /// Defaults to the sender
+    pub treasury: Option<String>,
 }
 
 pub type TokenId = String;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     Increment {},
     Reset { count: i32 },
+    /// Sets the royalty of token_id when given, keeps the current one otherwise
     Mint {
         to: String,
         token_id: TokenId,
         value: Uint128,
         msg: Option<Binary>,
+        royalty: Option<RoyaltyMsg>,
     },
+    /// Replace or remove the royalty of token_id, minter only
+    UpdateRoyalty { token_id: TokenId, royalty: Option<RoyaltyMsg> },
     SendFrom {
         from: String,
         to: String,
@@ -40,15 +62,217 @@ pub enum ExecuteMsg {
         expires: Option<Expiration>,
     },
     RevokeAll { operator: String },
+    /// Returns the new auction id as the `auction_id` attribute and as response data
     CreateAuction {
         token_id: TokenId,
         amount: Uint128,
-        price: Coin,
+        reserve_price: Coin,
         seller: String,
+        /// Bidding opens immediately without one
+        bidding_start: Option<Expiration>,
         bidding_close: Expiration,
+        min_increment: Option<BidIncrement>,
+        anti_snipe: Option<AntiSnipe>,
+        buy_now_price: Option<Coin>,
+        /// Defaults to an English auction
+        kind: Option<AuctionKind>,
+        /// Shares of the proceeds after fees, summing to 10000 bps.
+        /// Defaults to the seller taking all.
+        payees: Option<Vec<PayeeMsg>>,
+    },
+    Bid { auction_id: u64 },
+    CloseAuction { auction_id: u64 },
+    /// Withdraw an auction that has no bids yet, returning the escrowed tokens.
+    /// Only the seller or an approved operator can cancel.
+    CancelAuction { auction_id: u64 },
+    /// Seal a bid on a sealed-bid auction until bidding_close, see `bid_commitment`.
+    /// The funds sent are the deposit and must cover the revealed amount.
+    CommitBid { auction_id: u64, commitment: Binary },
+    /// Open a sealed bid after bidding_close until reveal_close
+    RevealBid { auction_id: u64, amount: Uint128, salt: String },
+    /// Bid on a multi-unit auction, sending quantity times unit price.
+    /// Replaces and refunds an earlier bid from the same bidder.
+    BidUnits { auction_id: u64, quantity: Uint128, unit_price: Uint128 },
+    /// Delete up to `limit` bid history entries of a settled, cancelled or unsold auction.
+    /// Anyone can prune.
+    PruneBids { auction_id: u64, limit: Option<u32> },
+    /// Pay out the refunds and proceeds claimable by the sender in `denom`,
+    /// the token address for CW20 tokens
+    Withdraw { denom: String },
+    /// Pay out everything claimable by the sender
+    WithdrawAll {},
+    /// Change the native denoms new auctions can be priced in, owner only.
+    /// Auctions already created keep their denom.
+    UpdateAcceptedDenoms { add: Vec<String>, remove: Vec<String> },
+    /// Change the CW20 tokens new auctions can be priced in, owner only
+    UpdateCw20Whitelist { add: Vec<String>, remove: Vec<String> },
+    /// Change the fee taken from sales settled from now on, owner only
+    UpdateProtocolFee { protocol_fee_bps: u64, treasury: String },
+    /// Escrow units of a token of this contract held by `seller` for sale at unit_price each,
+    /// only the seller or an approved operator can list.
+    /// Returns the new listing id as the `listing_id` attribute and as response data
+    ListForSale { seller: String, token_id: TokenId, amount: Uint128, unit_price: Coin },
+    /// Buy some or all of the units left, sending amount times the unit price
+    Buy { listing_id: u64, amount: Uint128 },
+    /// Return the units left to the seller, only the seller or an approved operator can delist
+    Delist { listing_id: u64 },
+    /// Offer the funds sent for `amount` units of token_id held by `owner`, escrowed until
+    /// accepted, withdrawn or refunded after `expires`.
+    /// Returns the new offer id as the `offer_id` attribute and as response data
+    MakeOffer { owner: String, token_id: TokenId, amount: Uint128, expires: Expiration },
+    /// Sell the units to the offerer, only the owner or an approved operator can accept
+    AcceptOffer { offer_id: u64 },
+    /// Refund the offer to the offerer, anyone can once it has expired
+    WithdrawOffer { offer_id: u64 },
+    /// Offer unit_price for each of `quantity` units of any token in `scope`,
+    /// sending unit_price times quantity to escrow.
+    /// Returns the new offer id as the `offer_id` attribute and as response data
+    MakeCollectionOffer { scope: OfferScope, unit_price: Coin, quantity: Uint128, expires: Expiration },
+    /// Sell `amount` units of token_id held by `owner` into the offer,
+    /// only the owner or an approved operator can fill
+    FillCollectionOffer { offer_id: u64, owner: String, token_id: TokenId, amount: Uint128 },
+    /// Refund what is left of the offer to the offerer, anyone can once it has expired
+    WithdrawCollectionOffer { offer_id: u64 },
+    /// Create auctions for tokens sent with a `ReceiveMsg` payload,
+    /// accepted from this contract or any external cw1155 contract.
+    /// Also takes bids in CW20 tokens sent with a `Cw20HookMsg` payload.
+    Receive(TokenReceiveMsg),
+    BatchReceive(Cw1155BatchReceiveMsg),
+}
+
+/// Payload embedded in the `msg` of a `Receive` or `BatchReceive`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    /// List the received tokens, the sender of the tokens becomes the seller.
+    /// A batch creates one auction per token_id on the same terms.
+    /// The new auction ids are returned like for `ExecuteMsg::CreateAuction`,
+    /// as a list in the response data of a batch.
+    CreateAuction {
+        reserve_price: Coin,
+        bidding_start: Option<Expiration>,
+        bidding_close: Expiration,
+        min_increment: Option<BidIncrement>,
+        anti_snipe: Option<AntiSnipe>,
+        buy_now_price: Option<Coin>,
+        /// Defaults to an English auction
+        kind: Option<AuctionKind>,
+        /// Shares of the proceeds after fees, summing to 10000 bps.
+        /// Defaults to the seller taking all.
+        payees: Option<Vec<PayeeMsg>>,
     },
-    Bid { token_id: TokenId, seller: String, },
-    CloseAuction { token_id: TokenId, seller: String, },
+}
+
+/// cw1155 and CW20 contracts both call `Receive`, this holds the fields of either
+/// as the json decoder of the contract cannot tell untagged variants apart.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TokenReceiveMsg {
+    /// cw1155 only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_id: Option<TokenId>,
+    /// CW20 only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    pub amount: Uint128,
+    pub msg: Binary,
+}
+
+/// A `TokenReceiveMsg` by the kind of token received
+pub enum TokenReceived {
+    Cw1155(Cw1155ReceiveMsg),
+    Cw20(Cw20ReceiveMsg),
+}
+
+impl TokenReceiveMsg {
+    /// CW20 hooks carry a sender, cw1155 hooks an operator and token_id
+    pub fn into_received(self) -> StdResult<TokenReceived> {
+        match (self.sender, self.operator, self.token_id) {
+            (Some(sender), None, None) => Ok(TokenReceived::Cw20(Cw20ReceiveMsg {
+                sender,
+                amount: self.amount,
+                msg: self.msg,
+            })),
+            (None, Some(operator), Some(token_id)) => Ok(TokenReceived::Cw1155(Cw1155ReceiveMsg {
+                operator,
+                from: self.from,
+                token_id,
+                amount: self.amount,
+                msg: self.msg,
+            })),
+            _ => Err(StdError::generic_err("receive needs a sender or an operator and token_id")),
+        }
+    }
+}
+
+impl From<Cw1155ReceiveMsg> for TokenReceiveMsg {
+    fn from(msg: Cw1155ReceiveMsg) -> Self {
+        TokenReceiveMsg {
+            operator: Some(msg.operator),
+            from: msg.from,
+            token_id: Some(msg.token_id),
+            sender: None,
+            amount: msg.amount,
+            msg: msg.msg,
+        }
+    }
+}
+
+impl From<Cw20ReceiveMsg> for TokenReceiveMsg {
+    fn from(msg: Cw20ReceiveMsg) -> Self {
+        TokenReceiveMsg {
+            operator: None,
+            from: None,
+            token_id: None,
+            sender: Some(msg.sender),
+            amount: msg.amount,
+            msg: msg.msg,
+        }
+    }
+}
+
+/// Payload embedded in the `msg` of a CW20 `Send`, the sent tokens are the funds of the bid
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    Bid { auction_id: u64 },
+    CommitBid { auction_id: u64, commitment: Binary },
+    BidUnits { auction_id: u64, quantity: Uint128, unit_price: Uint128 },
+    Buy { listing_id: u64, amount: Uint128 },
+    MakeOffer { owner: String, token_id: TokenId, amount: Uint128, expires: Expiration },
+    MakeCollectionOffer { scope: OfferScope, unit_price: Coin, quantity: Uint128, expires: Expiration },
+}
+
+/// Royalty paid to `recipient` on every auction sale
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltyMsg {
+    pub recipient: String,
+    /// Share of the sale price, in basis points
+    pub bps: u64,
+}
+
+/// Takes `share_bps` of the proceeds of an auction
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PayeeMsg {
+    pub address: String,
+    pub share_bps: u64,
+}
+
+/// Terms shared by every way of creating an auction
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuctionTerms {
+    pub reserve_price: Coin,
+    pub bidding_start: Option<Expiration>,
+    pub bidding_close: Expiration,
+    pub min_increment: Option<BidIncrement>,
+    pub anti_snipe: Option<AntiSnipe>,
+    pub buy_now_price: Option<Coin>,
+    pub kind: Option<AuctionKind>,
+    pub payees: Option<Vec<PayeeMsg>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -56,13 +280,146 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     // GetCount returns the current count as a json-encoded number
     GetCount {},
+    /// Returns the contract Config with the accepted denoms and tokens
+    Config {},
     /// Returns the current balance of the given address, 0 if unset.
     /// Return type: BalanceResponse.
     Balance { owner: String, token_id: TokenId },
     /// Query approved status `owner` granted to `operator`.
     /// Return type: IsApprovedForAllResponse
     IsApprovedForAll { owner: String, operator: String },
-    Auction { seller: String, token_id: TokenId },
+    Auction { auction_id: u64 },
+    /// All auctions by id, the following queries are all paginated by id
+    /// within what they match.
+    /// Return type: AuctionsResponse
+    ListAuctions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    AuctionsBySeller {
+        seller: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    AuctionsByToken {
+        token_id: TokenId,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Auctions the bidder is currently winning.
+    /// Multi-unit auctions have no single highest bidder and are never listed.
+    AuctionsByBidder {
+        bidder: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Every accepted bid on the auction, oldest first, paginated by bid id.
+    /// Return type: BidsResponse
+    BidHistory {
+        auction_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Every accepted bid of the bidder, paginated by `(auction_id, bid_id)`.
+    /// Return type: BidsResponse
+    BidsByBidder {
+        bidder: String,
+        start_after: Option<(u64, u64)>,
+        limit: Option<u32>,
+    },
+    /// Refunds and proceeds waiting to be withdrawn by `address`.
+    /// Return type: ClaimableResponse
+    Claimable { address: String },
+    /// CW2981 royalty owed to the creator when token_id sells for sale_price.
+    /// Return type: RoyaltiesInfoResponse
+    RoyaltyInfo { token_id: TokenId, sale_price: Uint128 },
+    /// Auctions in the status at the queried block, at most `limit` out of
+    /// the next auctions scanned. Page on from `last_scanned`.
+    /// Return type: AuctionsByStatusResponse
+    AuctionsByStatus {
+        status: AuctionStatus,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Auctions whose bidding closes before `before`, soonest first,
+    /// paginated by `(bidding_close, auction_id)`.
+    /// Only auctions closing in the unit of `before` are listed.
+    /// Return type: AuctionsResponse
+    AuctionsEndingBefore {
+        before: Expiration,
+        start_after: Option<(Expiration, u64)>,
+        limit: Option<u32>,
+    },
+    /// Return type: ListingResponse
+    Listing { listing_id: u64 },
+    /// All listings by id, the following listing queries are all paginated by id
+    /// within what they match.
+    /// Return type: ListingsResponse
+    ListListings {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    ListingsBySeller {
+        seller: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    ListingsByToken {
+        token_id: TokenId,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Return type: OfferResponse
+    Offer { offer_id: u64 },
+    /// Open offers on token_id, including expired ones not yet refunded,
+    /// the following offer query is also paginated by id.
+    /// Return type: OffersResponse
+    OffersByToken {
+        token_id: TokenId,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    OffersByOfferer {
+        offerer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Return type: CollectionOfferResponse
+    CollectionOffer { offer_id: u64 },
+    /// All open collection offers by id, the following collection offer queries
+    /// are all paginated by id within what they match.
+    /// Return type: CollectionOffersResponse
+    ListCollectionOffers {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    CollectionOffersByOfferer {
+        offerer: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Collection offers token_id can fill
+    CollectionOffersForToken {
+        token_id: TokenId,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Return type: CommitmentResponse
+    Commitment { auction_id: u64, bidder: String },
+    /// Paginated by bidder.
+    /// Return type: CommitmentsResponse
+    Commitments {
+        auction_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Paginated by bidder.
+    /// Return type: UnitBidsResponse
+    UnitBids {
+        auction_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -74,6 +431,8 @@ pub struct CountResponse {
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct BalanceResponse {
     pub balance: Uint128,
+    /// Units held in escrow by the contract on behalf of the owner
+    pub escrowed: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -83,7 +442,97 @@ pub struct IsApprovedForAllResponse {
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct AuctionResponse {
+    pub auction_id: u64,
     pub auction: Auction,
+    /// The status at the queried block
+    pub status: AuctionStatus,
+    /// The lowest bid the auction accepts at the queried block,
+    /// the live price for Dutch auctions
+    pub next_min_bid: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AuctionsResponse {
+    pub auctions: Vec<AuctionResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AuctionsByStatusResponse {
+    pub auctions: Vec<AuctionResponse>,
+    /// The last auction id scanned, None once no auctions are left to scan
+    pub last_scanned: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ListingResponse {
+    pub listing_id: u64,
+    pub listing: Listing,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ListingsResponse {
+    pub listings: Vec<ListingResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OfferResponse {
+    pub offer_id: u64,
+    pub offer: Offer,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OffersResponse {
+    pub offers: Vec<OfferResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CollectionOfferResponse {
+    pub offer_id: u64,
+    pub offer: CollectionOffer,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CollectionOffersResponse {
+    pub offers: Vec<CollectionOfferResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BidsResponse {
+    pub bids: Vec<BidRecord>,
+}
+
+/// Same shape as CW2981, no royalty is an empty address and zero amount
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RoyaltiesInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ClaimableResponse {
+    pub claimable: Vec<Coin>,
+    pub cw20: Vec<Cw20CoinVerified>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CommitmentResponse {
+    pub commitment: Commitment,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CommitmentsResponse {
+    pub commitments: Vec<Commitment>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct UnitBidsResponse {
+    pub bids: Vec<UnitBid>,
+}
+
+/// The commitment to send with `CommitBid`, sha256 of `{bidder}:{amount}:{salt}`
+pub fn bid_commitment(bidder: &str, amount: Uint128, salt: &str) -> Binary {
+    let preimage = format!("{}:{}:{}", bidder, amount, salt);
+    Binary::from(Sha256::digest(preimage.as_bytes()).to_vec())
 }
 
 /// Cw1155ReceiveMsg should be de/serialized under `Receive()` variant in a ExecuteMsg
@@ -1,9 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128, Coin};
-use cw0::Expiration;
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{coin, Addr, Binary, BlockInfo, Uint128, Coin, StdResult, Timestamp, Storage, Empty};
+use std::fmt;
+use cw0::{Duration, Expiration};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, U64Key, U8Key};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
@@ -13,6 +14,21 @@ pub struct State {
 
 pub const STATE: Item<State> = Item::new("state");
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    /// Applied to auctions created without their own increment
+    pub default_min_increment: BidIncrement,
+    /// Native denoms auctions can be priced in
+    pub accepted_denoms: Vec<String>,
+    /// CW20 tokens auctions can be priced in
+    pub cw20_whitelist: Vec<Addr>,
+    /// Share of every sale kept for the treasury, in basis points
+    pub protocol_fee_bps: u64,
+    pub treasury: Addr,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
 /// Store the minter address who have permission to mint new tokens.
 pub const MINTER: Item<Addr> = Item::new("minter");
 /// Store the balance map, `(owner, token_id) -> balance`
@@ -22,14 +38,633 @@ pub const APPROVES: Map<(&Addr, &Addr), Expiration> = Map::new("approves");
 /// Store the tokens metadata url, also supports enumerating tokens,
 /// An entry for token_id must exist as long as there's tokens in circulation.
 pub const TOKENS: Map<&str, String> = Map::new("tokens");
+/// Store the escrowed balance map, `(owner, token_id) -> balance`,
+/// the tokens themselves are held in BALANCES under the contract address.
+pub const ESCROWS: Map<(&Addr, &str), Uint128> = Map::new("escrows");
+/// Store the royalty owed on every sale of a token, `token_id -> royalty`
+pub const ROYALTIES: Map<&str, Royalty> = Map::new("royalties");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Royalty {
+    pub recipient: Addr,
+    /// Share of the sale price, in basis points
+    pub bps: u64,
+}
+/// Store the refunds and proceeds waiting to be withdrawn, `(owner, denom) -> amount`
+pub const CLAIMABLE: Map<(&Addr, &str), Uint128> = Map::new("claimable");
+/// Same as CLAIMABLE for CW20 tokens, `(owner, token) -> amount`
+pub const CLAIMABLE_CW20: Map<(&Addr, &Addr), Uint128> = Map::new("claimable_cw20");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Auction {
+    pub seller: Addr,
+    /// The cw1155 contract holding the auctioned tokens, this contract for local tokens
+    pub collection: Addr,
+    pub token_id: String,
     pub amount: Uint128,
+    /// The lowest acceptable first bid
+    pub reserve_price: Coin,
+    /// The highest bid so far, equal to the reserve price until the first bid
     pub price: Coin,
-    pub highest_bidder: Addr,
+    /// None until the first bid lands
+    pub highest_bidder: Option<Addr>,
+    /// Bids are rejected until this expires, in the unit of bidding_close
+    pub bidding_start: Option<Expiration>,
     pub bidding_close: Expiration,
+    /// How far a new bid has to exceed the highest bid
+    pub min_increment: BidIncrement,
+    pub anti_snipe: Option<AntiSnipe>,
+    /// A bid of at least this much ends the auction right away
+    pub buy_now_price: Option<Coin>,
+    pub kind: AuctionKind,
+    /// Priced in the CW20 token at the address in the price denom, bids arrive
+    /// through the token's `Send` hook and never as native coins.
+    pub cw20: bool,
+    /// Split the seller's proceeds, the seller takes them all when empty
+    pub payees: Vec<Payee>,
+    /// Active until settled or cancelled, see `status_at` for the live status
+    pub status: AuctionStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Payee {
+    pub address: Addr,
+    /// Share of the proceeds, in basis points of the total
+    pub share_bps: u64,
+}
+
+impl Auction {
+    /// The status at `block`, an active auction is scheduled until bidding opens
+    /// and has ended once bidding closed
+    pub fn status_at(&self, block: &BlockInfo) -> AuctionStatus {
+        match self.status {
+            AuctionStatus::Active {} if self.bidding_close.is_expired(block) => AuctionStatus::Ended {},
+            AuctionStatus::Active {} if self.bidding_start.is_some_and(|start| !start.is_expired(block)) => {
+                AuctionStatus::Scheduled {}
+            }
+            _ => self.status.clone(),
+        }
+    }
+
+    /// The lowest bid the auction accepts at `block`,
+    /// the reserve price until the first bid lands,
+    /// the live price for Dutch auctions.
+    pub fn next_min_bid(&self, block: &BlockInfo) -> StdResult<Coin> {
+        if let AuctionKind::Dutch(dutch) = &self.kind {
+            return Ok(dutch.price_at(&self.reserve_price, block));
+        }
+        if self.highest_bidder.is_none() {
+            return Ok(self.reserve_price.clone());
+        }
+        let increment = match self.min_increment {
+            BidIncrement::Absolute { amount } => amount,
+            BidIncrement::BasisPoints { bps } => self.price.amount.multiply_ratio(bps, 10_000u64),
+        };
+        // a new bid always has to beat the highest bid
+        let increment = std::cmp::max(increment, Uint128::new(1));
+        let min_bid = self.price.amount.checked_add(increment)?;
+        // meeting the buy now price always takes the auction
+        match &self.buy_now_price {
+            Some(buy_now_price) if buy_now_price.amount < min_bid => Ok(buy_now_price.clone()),
+            _ => Ok(coin(min_bid.u128(), &self.price.denom)),
+        }
+    }
+
+    /// Whether the highest bid meets the buy now price
+    pub fn buy_now_reached(&self) -> bool {
+        match (&self.buy_now_price, &self.highest_bidder) {
+            (Some(buy_now_price), Some(_)) => self.price.amount >= buy_now_price.amount,
+            _ => false,
+        }
+    }
+
+    /// Settlement is possible once this expires,
+    /// the end of the reveal phase for sealed-bid auctions.
+    pub fn settle_after(&self) -> Expiration {
+        match &self.kind {
+            AuctionKind::Sealed(sealed) => sealed.reveal_close,
+            _ => self.bidding_close,
+        }
+    }
+
+    /// Move bidding_close out for a bid landing inside the anti-snipe window,
+    /// returns the new close when it moved.
+    pub fn extend_close(&mut self, block: &BlockInfo) -> StdResult<Option<Expiration>> {
+        let anti_snipe = match &self.anti_snipe {
+            None => return Ok(None),
+            Some(anti_snipe) => anti_snipe,
+        };
+        if self.bidding_close > anti_snipe.window.after(block) {
+            return Ok(None);
+        }
+        let mut new_close = (self.bidding_close + anti_snipe.extend_by)?;
+        if new_close > anti_snipe.max_close {
+            new_close = anti_snipe.max_close;
+        }
+        if new_close == self.bidding_close {
+            return Ok(None);
+        }
+        self.bidding_close = new_close;
+        Ok(Some(new_close))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionStatus {
+    /// Bidding has not opened yet
+    Scheduled {},
+    /// Taking bids
+    Active {},
+    /// Bidding closed, waiting for settlement
+    Ended {},
+    /// Sold and paid out
+    Settled {},
+    /// Withdrawn by the seller before any bid
+    Cancelled {},
+    /// Closed without a winning bid, the tokens went back to the seller
+    NoSale {},
+}
+
+impl AuctionStatus {
+    /// The status stored on the auction, live statuses are all stored as active
+    pub fn stored(&self) -> AuctionStatus {
+        match self {
+            AuctionStatus::Scheduled {} | AuctionStatus::Active {} | AuctionStatus::Ended {} => AuctionStatus::Active {},
+            status => status.clone(),
+        }
+    }
+}
+
+impl fmt::Display for AuctionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuctionStatus::Scheduled {} => write!(f, "scheduled"),
+            AuctionStatus::Active {} => write!(f, "active"),
+            AuctionStatus::Ended {} => write!(f, "ended"),
+            AuctionStatus::Settled {} => write!(f, "settled"),
+            AuctionStatus::Cancelled {} => write!(f, "cancelled"),
+            AuctionStatus::NoSale {} => write!(f, "no_sale"),
+        }
+    }
+}
+
+/// Extends bidding when bids land near the end, all in the unit of bidding_close
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AntiSnipe {
+    /// Bids landing this close to bidding_close extend it
+    pub window: Duration,
+    /// How far bidding_close moves out per extension
+    pub extend_by: Duration,
+    /// bidding_close never moves past this point
+    pub max_close: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BidIncrement {
+    /// Fixed amount of the auction denom
+    Absolute { amount: Uint128 },
+    /// Basis points of the highest bid, at most 10000
+    BasisPoints { bps: u64 },
+}
+
+impl Default for BidIncrement {
+    fn default() -> Self {
+        BidIncrement::Absolute { amount: Uint128::zero() }
+    }
+}
+
+/// Store the last auction id handed out, ids start at 1
+pub const AUCTION_COUNT: Item<u64> = Item::new("auction_count");
+/// Secondary indexes of the auctions map, the index keys end with the auction id
+pub struct AuctionIndexes<'a> {
+    pub seller: MultiIndex<'a, (Vec<u8>, Vec<u8>), Auction>,
+    pub token_id: MultiIndex<'a, (Vec<u8>, Vec<u8>), Auction>,
+    /// Auctions without bids are indexed under an empty bidder
+    pub highest_bidder: MultiIndex<'a, (Vec<u8>, Vec<u8>), Auction>,
+    /// `(unit of bidding_close, bidding_close ++ auction_id)`, see `indexed_close`
+    pub bidding_close: MultiIndex<'a, (U8Key, Vec<u8>), Auction>,
+    /// The stored status
+    pub status: MultiIndex<'a, (Vec<u8>, Vec<u8>), Auction>,
+}
+
+impl<'a> IndexList<Auction> for AuctionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Auction>> + '_> {
+        let v: Vec<&dyn Index<Auction>> = vec![
+            &self.seller,
+            &self.token_id,
+            &self.highest_bidder,
+            &self.bidding_close,
+            &self.status,
+        ];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Store the auctions map, `auction_id -> Auction`
+pub fn auctions<'a>() -> IndexedMap<'a, U64Key, Auction, AuctionIndexes<'a>> {
+    let indexes = AuctionIndexes {
+        seller: MultiIndex::new(
+            |auction, pk| (auction.seller.as_bytes().to_vec(), pk),
+            "auctions",
+            "auctions__seller",
+        ),
+        token_id: MultiIndex::new(
+            |auction, pk| (auction.token_id.as_bytes().to_vec(), pk),
+            "auctions",
+            "auctions__token_id",
+        ),
+        highest_bidder: MultiIndex::new(
+            |auction, pk| (auction.highest_bidder.as_ref().map(|bidder| bidder.as_bytes().to_vec()).unwrap_or_default(), pk),
+            "auctions",
+            "auctions__highest_bidder",
+        ),
+        bidding_close: MultiIndex::new(
+            |auction, pk| {
+                let (unit, close) = indexed_close(&auction.bidding_close);
+                (unit, [&close.to_be_bytes()[..], &pk].concat())
+            },
+            "auctions",
+            "auctions__bidding_close",
+        ),
+        status: MultiIndex::new(
+            |auction, pk| (auction.status.to_string().into_bytes(), pk),
+            "auctions",
+            "auctions__status",
+        ),
+    };
+    IndexedMap::new("auctions", indexes)
+}
+
+/// Split an expiration into the unit it is indexed under and its value,
+/// block heights under 0, nanoseconds under 1, never under 2.
+pub fn indexed_close(expiration: &Expiration) -> (U8Key, u64) {
+    match expiration {
+        Expiration::AtHeight(height) => (0u8.into(), *height),
+        Expiration::AtTime(time) => (1u8.into(), time.nanos()),
+        Expiration::Never {} => (2u8.into(), 0),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionKind {
+    /// Ascending bids, the highest bidder wins at bidding_close
+    English {},
+    /// Descending price, the first buyer at the live price wins
+    Dutch(DutchAuction),
+    /// Hashed bids until bidding_close, revealed until reveal_close
+    Sealed(SealedAuction),
+    /// Bids for a quantity at a unit price, the top bids fill the units
+    /// at the unit price of the lowest winning bid. The reserve price is per unit.
+    MultiUnit {},
+}
+
+impl Default for AuctionKind {
+    fn default() -> Self {
+        AuctionKind::English {}
+    }
+}
+
+/// Price falls from `start_price` at `decay_start` to the reserve price at `decay_end`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DutchAuction {
+    pub start_price: Coin,
+    pub decay_start: Expiration,
+    pub decay_end: Expiration,
+    pub decay: PriceDecay,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceDecay {
+    /// Price falls a little every block or second
+    Linear {},
+    /// Price falls in `steps` equal drops
+    Stepped { steps: u64 },
+}
+
+impl DutchAuction {
+    /// The price at `block`, never below `floor`
+    pub fn price_at(&self, floor: &Coin, block: &BlockInfo) -> Coin {
+        let (elapsed, total) = match (self.decay_start, self.decay_end) {
+            (Expiration::AtHeight(start), Expiration::AtHeight(end)) => {
+                (block.height.saturating_sub(start), end.saturating_sub(start))
+            }
+            (Expiration::AtTime(start), Expiration::AtTime(end)) => (
+                block.time.seconds().saturating_sub(start.seconds()),
+                end.seconds().saturating_sub(start.seconds()),
+            ),
+            // mixed or unbounded decay is rejected on creation
+            _ => (0, 0),
+        };
+        if total == 0 || elapsed >= total {
+            return floor.clone();
+        }
+        let range = self.start_price.amount.saturating_sub(floor.amount);
+        let drop = match self.decay {
+            PriceDecay::Linear {} => range.multiply_ratio(elapsed, total),
+            PriceDecay::Stepped { steps } => {
+                let steps_done = (elapsed as u128 * steps as u128 / total as u128) as u64;
+                range.multiply_ratio(steps_done, steps)
+            }
+        };
+        coin((self.start_price.amount - drop).u128(), &self.start_price.denom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SealedAuction {
+    /// Commitments are revealed after bidding_close until this expires
+    pub reveal_close: Expiration,
+    pub pricing: SealedPricing,
+    /// Applied to the deposits of unrevealed or invalid commitments
+    pub penalty: CommitmentPenalty,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SealedPricing {
+    /// The winner pays their own bid
+    FirstPrice {},
+    /// The winner pays the second highest bid, or the reserve price (Vickrey)
+    SecondPrice {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitmentPenalty {
+    /// The deposit goes back to the bidder
+    Refund {},
+    /// The deposit goes to the seller
+    Forfeit {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Commitment {
+    pub bidder: Addr,
+    /// sha256 of `{bidder}:{amount}:{salt}`
+    pub hash: Binary,
+    /// Funds sent with the commitment, the revealed amount must not exceed it
+    pub deposit: Coin,
+    pub status: CommitmentStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitmentStatus {
+    Sealed {},
+    Revealed { amount: Uint128 },
+    /// Revealed below the reserve price or above the deposit
+    Invalid {},
 }
 
-/// Store the AUCTIONS map, `token_id -> Auction`
-pub const AUCTIONS: Map<(&Addr, &str), Auction> = Map::new("auctions");
+/// Store the sealed bids, `(auction_id, bidder) -> Commitment`
+pub const COMMITMENTS: Map<(U64Key, &Addr), Commitment> = Map::new("commitments");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnitBid {
+    pub bidder: Addr,
+    pub quantity: Uint128,
+    pub unit_price: Uint128,
+    /// Funds sent with the bid, at least quantity times unit price
+    pub deposit: Coin,
+    /// Block height the bid was placed at, earlier bids win ties
+    pub height: u64,
+}
+
+/// Store the multi-unit bids, `(auction_id, bidder) -> UnitBid`
+pub const UNIT_BIDS: Map<(U64Key, &Addr), UnitBid> = Map::new("unit_bids");
+
+/// Units of an external collection sent to a buyer, paid for once the `SendFrom` went through
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingSale {
+    pub auction_id: u64,
+    pub buyer: Addr,
+    pub price: Coin,
+}
+
+/// Store the last pending sale id handed out, used as reply id, ids start at 1
+pub const PENDING_SALE_COUNT: Item<u64> = Item::new("pending_sale_count");
+
+/// Store the sales waiting on their `SendFrom`, `reply id -> PendingSale`
+pub const PENDING_SALES: Map<U64Key, PendingSale> = Map::new("pending_sales");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidRecord {
+    pub auction_id: u64,
+    /// Increases with every bid on any auction
+    pub bid_id: u64,
+    pub bidder: Addr,
+    /// The bid, the unit price for multi-unit auctions
+    pub amount: Coin,
+    /// Units bid for on multi-unit auctions
+    pub quantity: Option<Uint128>,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// Store the last bid id handed out, ids start at 1
+pub const BID_COUNT: Item<u64> = Item::new("bid_count");
+
+pub struct BidIndexes<'a> {
+    pub bidder: MultiIndex<'a, (Vec<u8>, Vec<u8>), BidRecord>,
+}
+
+impl<'a> IndexList<BidRecord> for BidIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<BidRecord>> + '_> {
+        let v: Vec<&dyn Index<BidRecord>> = vec![&self.bidder];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Store every accepted bid, `(auction_id, bid_id) -> BidRecord`
+pub fn bids<'a>() -> IndexedMap<'a, (U64Key, U64Key), BidRecord, BidIndexes<'a>> {
+    let indexes = BidIndexes {
+        bidder: MultiIndex::new(
+            |bid, pk| (bid.bidder.as_bytes().to_vec(), pk),
+            "bids",
+            "bids__bidder",
+        ),
+    };
+    IndexedMap::new("bids", indexes)
+}
+
+/// Units of a token for sale at a fixed unit price, escrowed until bought or delisted
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Listing {
+    pub seller: Addr,
+    pub token_id: String,
+    /// Units left for sale
+    pub amount: Uint128,
+    pub unit_price: Coin,
+    /// Priced in the CW20 token at the address in the price denom
+    pub cw20: bool,
+}
+
+/// Store the last listing id handed out, ids start at 1
+pub const LISTING_COUNT: Item<u64> = Item::new("listing_count");
+
+/// Secondary indexes of the listings map, the index keys end with the listing id
+pub struct ListingIndexes<'a> {
+    pub seller: MultiIndex<'a, (Vec<u8>, Vec<u8>), Listing>,
+    pub token_id: MultiIndex<'a, (Vec<u8>, Vec<u8>), Listing>,
+}
+
+impl<'a> IndexList<Listing> for ListingIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Listing>> + '_> {
+        let v: Vec<&dyn Index<Listing>> = vec![&self.seller, &self.token_id];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Store the listings map, `listing_id -> Listing`, sold out listings are removed
+pub fn listings<'a>() -> IndexedMap<'a, U64Key, Listing, ListingIndexes<'a>> {
+    let indexes = ListingIndexes {
+        seller: MultiIndex::new(
+            |listing, pk| (listing.seller.as_bytes().to_vec(), pk),
+            "listings",
+            "listings__seller",
+        ),
+        token_id: MultiIndex::new(
+            |listing, pk| (listing.token_id.as_bytes().to_vec(), pk),
+            "listings",
+            "listings__token_id",
+        ),
+    };
+    IndexedMap::new("listings", indexes)
+}
+
+/// Funds escrowed for `amount` units of a token held by `owner`, until accepted,
+/// withdrawn or refunded after `expires`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Offer {
+    pub offerer: Addr,
+    pub owner: Addr,
+    pub token_id: String,
+    pub amount: Uint128,
+    /// The escrowed funds, paid in full for all units
+    pub price: Coin,
+    pub expires: Expiration,
+    /// Paid in the CW20 token at the address in the price denom
+    pub cw20: bool,
+}
+
+/// Store the last offer id handed out, ids start at 1
+pub const OFFER_COUNT: Item<u64> = Item::new("offer_count");
+
+/// Secondary indexes of the offers map, the index keys end with the offer id
+pub struct OfferIndexes<'a> {
+    pub token_id: MultiIndex<'a, (Vec<u8>, Vec<u8>), Offer>,
+    pub offerer: MultiIndex<'a, (Vec<u8>, Vec<u8>), Offer>,
+}
+
+impl<'a> IndexList<Offer> for OfferIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Offer>> + '_> {
+        let v: Vec<&dyn Index<Offer>> = vec![&self.token_id, &self.offerer];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Store the open offers, `offer_id -> Offer`, accepted and withdrawn offers are removed
+pub fn offers<'a>() -> IndexedMap<'a, U64Key, Offer, OfferIndexes<'a>> {
+    let indexes = OfferIndexes {
+        token_id: MultiIndex::new(
+            |offer, pk| (offer.token_id.as_bytes().to_vec(), pk),
+            "offers",
+            "offers__token_id",
+        ),
+        offerer: MultiIndex::new(
+            |offer, pk| (offer.offerer.as_bytes().to_vec(), pk),
+            "offers",
+            "offers__offerer",
+        ),
+    };
+    IndexedMap::new("offers", indexes)
+}
+
+/// The tokens a collection offer can be filled with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OfferScope {
+    /// Every token_id starting with `prefix`, the whole collection for an empty prefix
+    Prefix { prefix: String },
+    TokenIds { token_ids: Vec<String> },
+}
+
+impl OfferScope {
+    pub fn matches(&self, token_id: &str) -> bool {
+        match self {
+            OfferScope::Prefix { prefix } => token_id.starts_with(prefix.as_str()),
+            OfferScope::TokenIds { token_ids } => token_ids.iter().any(|id| id == token_id),
+        }
+    }
+
+    /// Index `offer_id` under every token_id or the prefix of the scope
+    pub fn index(&self, storage: &mut dyn Storage, offer_id: u64) -> StdResult<()> {
+        match self {
+            OfferScope::Prefix { prefix } => COLLECTION_OFFER_PREFIXES.save(storage, (prefix, offer_id.into()), &Empty {}),
+            OfferScope::TokenIds { token_ids } => token_ids
+                .iter()
+                .try_for_each(|id| COLLECTION_OFFER_TOKENS.save(storage, (id, offer_id.into()), &Empty {})),
+        }
+    }
+
+    /// Drop `offer_id` from the scope index
+    pub fn unindex(&self, storage: &mut dyn Storage, offer_id: u64) {
+        match self {
+            OfferScope::Prefix { prefix } => COLLECTION_OFFER_PREFIXES.remove(storage, (prefix, offer_id.into())),
+            OfferScope::TokenIds { token_ids } => token_ids
+                .iter()
+                .for_each(|id| COLLECTION_OFFER_TOKENS.remove(storage, (id, offer_id.into()))),
+        }
+    }
+}
+
+/// Index the open collection offers listing token ids, `(token_id, offer_id) -> Empty`
+pub const COLLECTION_OFFER_TOKENS: Map<(&str, U64Key), Empty> = Map::new("collection_offer_tokens");
+
+/// Index the open collection offers for a prefix, `(prefix, offer_id) -> Empty`
+pub const COLLECTION_OFFER_PREFIXES: Map<(&str, U64Key), Empty> = Map::new("collection_offer_prefixes");
+
+/// `unit_price` for each of `quantity` units of any token in `scope`,
+/// escrowed for every unit still wanted until filled, withdrawn or refunded after `expires`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionOffer {
+    pub offerer: Addr,
+    pub scope: OfferScope,
+    pub unit_price: Coin,
+    /// Units still wanted
+    pub quantity: Uint128,
+    pub expires: Expiration,
+    /// Paid in the CW20 token at the address in the price denom
+    pub cw20: bool,
+}
+
+/// Store the last collection offer id handed out, ids start at 1
+pub const COLLECTION_OFFER_COUNT: Item<u64> = Item::new("collection_offer_count");
+
+pub struct CollectionOfferIndexes<'a> {
+    pub offerer: MultiIndex<'a, (Vec<u8>, Vec<u8>), CollectionOffer>,
+}
+
+impl<'a> IndexList<CollectionOffer> for CollectionOfferIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<CollectionOffer>> + '_> {
+        let v: Vec<&dyn Index<CollectionOffer>> = vec![&self.offerer];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Store the open collection offers, `offer_id -> CollectionOffer`,
+/// filled and withdrawn offers are removed, from the scope index as well
+pub fn collection_offers<'a>() -> IndexedMap<'a, U64Key, CollectionOffer, CollectionOfferIndexes<'a>> {
+    let indexes = CollectionOfferIndexes {
+        offerer: MultiIndex::new(
+            |offer, pk| (offer.offerer.as_bytes().to_vec(), pk),
+            "collection_offers",
+            "collection_offers__offerer",
+        ),
+    };
+    IndexedMap::new("collection_offers", indexes)
+}